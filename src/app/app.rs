@@ -1,11 +1,18 @@
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::Duration;
 
 use super::config::*;
+use super::handler_editor::HandlerEditor;
+use crate::catalogue::CatalogueEntry;
+use crate::executable::ExecutableEntry;
 use crate::game::*;
+use crate::history::*;
 use crate::input::*;
 use crate::launch::launch_game;
+use crate::library::*;
 use crate::paths::*;
+use crate::presets::*;
 use crate::util::*;
 
 use eframe::egui::{self, Key};
@@ -17,12 +24,16 @@ pub enum MenuPage {
     Profiles,
     Game,
     Instances,
+    Catalogue,
+    Editor,
+    SteamImport,
 }
 
 #[derive(Eq, PartialEq)]
 pub enum SettingsPage {
     General,
     Gamescope,
+    Locations,
 }
 
 pub struct PartyApp {
@@ -37,12 +48,35 @@ pub struct PartyApp {
     pub instance_add_dev: Option<usize>,
     pub games: Vec<Game>,
     pub selected_game: usize,
+    pub library: Library,
+    pub history: Vec<SessionRecord>,
+    pub game_filter: GameFilter,
+    pub presets: Vec<Preset>,
+    pub preset_name: String,
     pub profiles: Vec<String>,
+    pub proton_installs: Vec<ProtonInstall>,
+    pub catalogue: Arc<Mutex<Vec<CatalogueEntry>>>,
+    pub steam_apps: Arc<Mutex<Vec<SteamApp>>>,
+    pub save_profile: usize,
+    pub overrides: GameOverrides,
+    pub overrides_uid: String,
+    pub overrides_env: String,
+    pub overrides_args: String,
+    // Working copy of the selected executable's properties, see display_executable_properties
+    pub exe_edit: ExecutableEntry,
+    pub exe_edit_env: String,
+    pub exe_edit_args: String,
+    pub game_locations: Vec<(String, String)>,
+    pub editor: HandlerEditor,
 
     pub loading_msg: Option<String>,
     pub loading_since: Option<std::time::Instant>,
+    // Progress of the running task, shown under the loading message
+    pub task_status: Arc<Mutex<String>>,
     #[allow(dead_code)]
     pub task: Option<std::thread::JoinHandle<()>>,
+    // Set by tasks that install or remove games, so the list is refreshed once they finish
+    pub rescan_games_after_task: bool,
 }
 
 macro_rules! cur_game {
@@ -66,10 +100,30 @@ impl Default for PartyApp {
             instance_add_dev: None,
             games: scan_all_games(),
             selected_game: 0,
+            library: load_library(),
+            history: load_history(),
+            game_filter: GameFilter::default(),
+            presets: load_presets(),
+            preset_name: String::new(),
             profiles: Vec::new(),
+            proton_installs: scan_proton_installs(),
+            catalogue: Arc::new(Mutex::new(Vec::new())),
+            steam_apps: Arc::new(Mutex::new(Vec::new())),
+            save_profile: 0,
+            overrides: GameOverrides::default(),
+            overrides_uid: String::new(),
+            overrides_env: String::new(),
+            overrides_args: String::new(),
+            exe_edit: ExecutableEntry::default(),
+            exe_edit_env: String::new(),
+            exe_edit_args: String::new(),
+            game_locations: Vec::new(),
+            editor: HandlerEditor::default(),
             loading_msg: None,
             loading_since: None,
+            task_status: Arc::new(Mutex::new(String::new())),
             task: None,
+            rescan_games_after_task: false,
         }
     }
 }
@@ -132,6 +186,9 @@ impl eframe::App for PartyApp {
                 MenuPage::Profiles => self.display_page_profiles(ui),
                 MenuPage::Game => self.display_page_game(ui),
                 MenuPage::Instances => self.display_page_instances(ui),
+                MenuPage::Catalogue => self.display_page_catalogue(ui),
+                MenuPage::Editor => self.display_page_editor(ui),
+                MenuPage::SteamImport => self.display_page_steam_import(ui),
             }
         });
 
@@ -140,6 +197,13 @@ impl eframe::App for PartyApp {
                 let _ = handle.join();
                 self.loading_since = None;
                 self.loading_msg = None;
+                // Launching updates the play count, last played time and session history
+                self.library = load_library();
+                self.history = load_history();
+                if self.rescan_games_after_task {
+                    self.rescan_games_after_task = false;
+                    self.games = scan_all_games();
+                }
             } else {
                 self.task = Some(handle);
             }
@@ -164,6 +228,10 @@ impl eframe::App for PartyApp {
                                 ui.add(egui::widgets::Spinner::new().size(40.0));
                                 ui.add_space(8.0);
                                 ui.label(msg);
+                                let status = self.task_status.lock().unwrap();
+                                if !status.is_empty() {
+                                    ui.label(egui::RichText::new(status.as_str()).weak());
+                                }
                             });
                         });
                 });
@@ -181,9 +249,16 @@ impl PartyApp {
     {
         self.loading_msg = Some(msg.to_string());
         self.loading_since = Some(std::time::Instant::now());
+        self.task_status.lock().unwrap().clear();
         self.task = Some(std::thread::spawn(f));
     }
 
+    // Lets a task report what it's doing, e.g. each prefix setup step
+    pub fn task_progress(&self) -> impl Fn(&str) + Send + 'static {
+        let status = self.task_status.clone();
+        move |s: &str| *status.lock().unwrap() = s.to_string()
+    }
+
     fn check_dependencies(&mut self) {
         if self.task.is_some() {
             return;
@@ -352,41 +427,82 @@ impl PartyApp {
         });
     }
 
+    // Shows the page of the game with the given uid, e.g. when started from a partydeck:// link
+    pub fn open_game(&mut self, uid: &str) {
+        match self.games.iter().position(|game| game.uid() == uid) {
+            Some(i) => {
+                self.selected_game = i;
+                self.cur_page = MenuPage::Game;
+            }
+            None => msg("Error", &format!("Game \"{uid}\" isn't installed")),
+        }
+    }
+
     pub fn prepare_game_launch(&mut self) {
         let game = cur_game!(self).to_owned();
         let mut instances = self.instances.clone();
-        let mut guests = GUEST_NAMES.to_vec();
+        let mut guests = guest_names(instances.len());
 
         for instance in &mut instances {
             if instance.profselection == 0 {
-                let i = fastrand::usize(..guests.len());
-                instance.profname = format!(".{}", guests[i]);
-                guests.swap_remove(i);
+                instance.profname = guests.pop().unwrap_or_default();
             } else {
                 instance.profname = self.profiles[instance.profselection].to_owned();
             }
         }
 
-        let dev_infos: Vec<DeviceInfo> = self
-            .input_devices
+        self.start_session(game, instances);
+    }
+
+    // Saves the current instances as a preset for the selected game
+    pub fn save_instances_preset(&mut self) {
+        let profiles: Vec<String> = self
+            .instances
             .iter()
-            .map(|p| DeviceInfo {
-                path: p.path().to_string(),
-                vendor: p.vendor(),
-                enabled: p.enabled(),
-                device_type: p.device_type(),
+            .map(|i| match i.profselection {
+                0 => String::new(),
+                sel => self.profiles[sel].clone(),
             })
             .collect();
+        let dev_infos: Vec<DeviceInfo> = self.input_devices.iter().map(|p| p.info()).collect();
+        let preset = Preset::from_instances(
+            &self.preset_name,
+            cur_game!(self).uid(),
+            &self.instances,
+            &profiles,
+            &dev_infos,
+        );
+        match save_preset(&preset) {
+            Ok(presets) => {
+                self.presets = presets;
+                self.infotext = format!("Saved preset \"{}\".", preset.name);
+            }
+            Err(err) => msg("Error", &format!("Couldn't save preset: {err}")),
+        }
+    }
+
+    pub fn launch_preset(&mut self, preset: &Preset) {
+        let game = cur_game!(self).to_owned();
+        let dev_infos: Vec<DeviceInfo> = self.input_devices.iter().map(|p| p.info()).collect();
+        match preset.instances(&dev_infos) {
+            Ok(instances) => self.start_session(game, instances),
+            Err(err) => msg("Launch Error", &format!("{err}")),
+        }
+    }
+
+    fn start_session(&mut self, game: Game, instances: Vec<Instance>) {
+        let dev_infos: Vec<DeviceInfo> = self.input_devices.iter().map(|p| p.info()).collect();
 
         let cfg = self.options.clone();
         let _ = save_cfg(&cfg);
+        let progress = self.task_progress();
 
         self.cur_page = MenuPage::Home;
         self.spawn_task(
             "Launching...\n\nDon't press any buttons or move any analog sticks or mice.",
             move || {
                 sleep(std::time::Duration::from_secs(2));
-                if let Err(err) = launch_game(&game, &dev_infos, &instances, &cfg) {
+                if let Err(err) = launch_game(&game, &dev_infos, &instances, &cfg, &progress) {
                     println!("{}", err);
                     msg("Launch Error", &format!("{err}"));
                 }
@@ -394,9 +510,3 @@ impl PartyApp {
         );
     }
 }
-
-static GUEST_NAMES: [&str; 21] = [
-    "Blinky", "Pinky", "Inky", "Clyde", "Beatrice", "Battler", "Ellie", "Joel", "Leon", "Ada",
-    "Madeline", "Theo", "Yokatta", "Wyrm", "Brodiee", "Supreme", "Conk", "Gort", "Lich", "Smores",
-    "Canary",
-];
@@ -23,6 +23,10 @@ pub struct PartyConfig {
     pub proton_version: String,
     #[serde(default)]
     pub vertical_two_player: bool,
+    #[serde(default)]
+    pub per_game_prefix: bool,
+    #[serde(default)]
+    pub handler_index_url: String,
     pub pad_filter_type: PadFilterType,
 }
 
@@ -44,6 +48,8 @@ pub fn load_cfg() -> PartyConfig {
         kbm_support: true,
         proton_version: "".to_string(),
         vertical_two_player: false,
+        per_game_prefix: false,
+        handler_index_url: "".to_string(),
         pad_filter_type: PadFilterType::NoSteamInput,
     }
 }
@@ -54,3 +60,72 @@ pub fn save_cfg(config: &PartyConfig) -> Result<(), Box<dyn Error>> {
     serde_json::to_writer_pretty(file, config)?;
     Ok(())
 }
+
+// Per-game settings that are merged over PartyConfig when launching. Fields left as None use the global setting.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct GameOverrides {
+    pub proton_version: Option<String>,
+    pub render_scale: Option<i32>,
+    pub vertical_two_player: Option<bool>,
+    pub force_sdl: Option<bool>,
+    pub gamescope_sdl_backend: Option<bool>,
+    // KEY=VALUE pairs exported before launching
+    pub env: Vec<String>,
+    // Appended to the game's arguments for every instance
+    pub args: Vec<String>,
+}
+
+impl GameOverrides {
+    pub fn apply(&self, cfg: &PartyConfig) -> PartyConfig {
+        let mut out = cfg.clone();
+        if let Some(proton_version) = &self.proton_version {
+            out.proton_version = proton_version.clone();
+        }
+        if let Some(render_scale) = self.render_scale {
+            out.render_scale = render_scale;
+        }
+        if let Some(vertical_two_player) = self.vertical_two_player {
+            out.vertical_two_player = vertical_two_player;
+        }
+        if let Some(force_sdl) = self.force_sdl {
+            out.force_sdl = force_sdl;
+        }
+        if let Some(gamescope_sdl_backend) = self.gamescope_sdl_backend {
+            out.gamescope_sdl_backend = gamescope_sdl_backend;
+        }
+        out
+    }
+}
+
+pub fn load_overrides(uid: &str) -> GameOverrides {
+    let path = PATH_PARTY
+        .join("game_overrides")
+        .join(format!("{uid}.json"));
+
+    if let Ok(file) = File::open(path)
+        && let Ok(overrides) = serde_json::from_reader::<_, GameOverrides>(BufReader::new(file))
+    {
+        return overrides;
+    }
+
+    GameOverrides::default()
+}
+
+pub fn save_overrides(uid: &str, overrides: &GameOverrides) -> Result<(), Box<dyn Error>> {
+    let dir = PATH_PARTY.join("game_overrides");
+    let path = dir.join(format!("{uid}.json"));
+
+    // Don't leave empty files behind for games that use the global settings
+    if *overrides == GameOverrides::default() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, overrides)?;
+    Ok(())
+}
@@ -1,8 +1,15 @@
 use super::app::{MenuPage, PartyApp, SettingsPage};
 use super::config::*;
-use crate::game::Game::*;
+use crate::catalogue::*;
+use crate::desktop_entries::*;
+use crate::executable::*;
+use crate::game::{Game::*, add_executable, scan_all_games};
+use crate::history::*;
 use crate::input::*;
+use crate::library::set_favorite;
 use crate::paths::*;
+use crate::presets::*;
+use crate::steam_shortcuts::add_steam_shortcut;
 use crate::util::*;
 
 use dialog::DialogBox;
@@ -39,12 +46,23 @@ impl PartyApp {
                 SettingsPage::Gamescope,
                 "Gamescope",
             );
+            if ui
+                .selectable_value(
+                    &mut self.settings_page,
+                    SettingsPage::Locations,
+                    "Game locations",
+                )
+                .clicked()
+            {
+                self.game_locations = saved_paths();
+            }
         });
         ui.separator();
 
         match self.settings_page {
             SettingsPage::General => self.display_settings_general(ui),
             SettingsPage::Gamescope => self.display_settings_gamescope(ui),
+            SettingsPage::Locations => self.display_settings_locations(ui),
         }
 
         ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
@@ -63,6 +81,8 @@ impl PartyApp {
                         kbm_support: true,
                         proton_version: "".to_string(),
                         vertical_two_player: false,
+                        per_game_prefix: false,
+                        handler_index_url: "".to_string(),
                         pad_filter_type: PadFilterType::NoSteamInput,
                     };
                     self.input_devices = scan_input_devices(&self.options.pad_filter_type);
@@ -80,7 +100,24 @@ impl PartyApp {
             .auto_shrink(false)
             .show(ui, |ui| {
                 for profile in &self.profiles {
-                    if ui.selectable_value(&mut 0, 0, profile).clicked() {
+                    let playtime = profile_playtime(&self.history, profile);
+                    let total: u64 = playtime.values().sum();
+                    let btn = ui.selectable_value(&mut 0, 0, profile);
+                    if total > 0 {
+                        ui.indent(profile, |ui| {
+                            ui.label(format!("⏱ {} played", format_duration(total)));
+                            for (uid, secs) in &playtime {
+                                let name = self
+                                    .games
+                                    .iter()
+                                    .find(|game| game.uid() == uid)
+                                    .map(|game| game.name())
+                                    .unwrap_or(uid.clone());
+                                ui.label(format!("{name}: {}", format_duration(*secs)));
+                            }
+                        });
+                    }
+                    if btn.clicked() {
                         if let Err(_) = std::process::Command::new("sh")
                             .arg("-c")
                             .arg(format!(
@@ -133,6 +170,18 @@ impl PartyApp {
                 self.instance_add_dev = None;
                 self.cur_page = MenuPage::Instances;
             }
+            let uid = cur_game!(self).uid().to_string();
+            let favorite = self.library.is_favorite(&uid);
+            if ui
+                .button(if favorite { "★" } else { "☆" })
+                .on_hover_text("Favorites are pinned to the top of the games list.")
+                .clicked()
+            {
+                match set_favorite(&uid, !favorite) {
+                    Ok(library) => self.library = library,
+                    Err(err) => msg("Error", &format!("Couldn't save favorite: {err}")),
+                }
+            }
             if let HandlerRef(h) = cur_game!(self) {
                 ui.add(egui::Separator::default().vertical());
                 if h.win {
@@ -144,9 +193,135 @@ impl PartyApp {
                 ui.label(format!("Author: {}", h.author));
                 ui.add(egui::Separator::default().vertical());
                 ui.label(format!("Version: {}", h.version));
+                if h.win && !h.prefix_setup.is_empty() {
+                    ui.add(egui::Separator::default().vertical());
+                    if ui
+                        .button("Set Up Prefix")
+                        .on_hover_text(format!(
+                            "Applies this handler's prefix setup steps: {}",
+                            h.prefix_setup.join(", ")
+                        ))
+                        .clicked()
+                    {
+                        let h = h.clone();
+                        let cfg = load_overrides(&h.uid).apply(&self.options);
+                        let progress = self.task_progress();
+                        self.spawn_task(
+                            &format!("Setting up Proton prefix for {}...", h.display()),
+                            move || {
+                                if let Err(err) = apply_prefix_setup(&h, &cfg, &progress) {
+                                    println!("Prefix setup failed: {}", err);
+                                    msg("Error", &format!("Prefix setup failed: {}", err));
+                                }
+                            },
+                        );
+                    }
+                }
             }
         });
 
+        let uid = cur_game!(self).uid();
+        if let Some(last) = last_session(&self.history, uid) {
+            let sessions = self.history.iter().filter(|r| r.uid == uid);
+            let total: u64 = sessions.clone().map(|r| r.duration).sum();
+            ui.label(format!(
+                "Last played {} for {} by {}. {} sessions, {} in total.",
+                format_ago(last.end),
+                format_duration(last.duration),
+                last.profiles.join(", "),
+                sessions.count(),
+                format_duration(total),
+            ));
+        }
+
+        if let Executable(entry) = cur_game!(self) {
+            let entry = entry.clone();
+            egui::CollapsingHeader::new("Properties").show(ui, |ui| {
+                self.display_executable_properties(ui, &entry);
+            });
+        }
+
+        {
+            let h = &*cur_game!(self).handler();
+            egui::CollapsingHeader::new("Saves").show(ui, |ui| {
+                let profiles = scan_profiles(false);
+                if profiles.is_empty() {
+                    ui.label("Create a profile to import or export save data.");
+                    return;
+                }
+                if self.save_profile >= profiles.len() {
+                    self.save_profile = 0;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("👤");
+                    egui::ComboBox::from_id_salt("save_profile").show_index(
+                        ui,
+                        &mut self.save_profile,
+                        profiles.len(),
+                        |i| profiles[i].clone(),
+                    );
+                    let name = profiles[self.save_profile].as_str();
+                    let import_btn = ui.button("Import Save...").on_hover_text("Copies existing save data from the game's normal locations (Proton prefix AppData/Documents, ~/.local/share, ~/.config, Steam userdata or the game folder) into this profile. Select the folder containing the game's data.");
+                    let export_btn = ui.button("Export Save...").on_hover_text("Copies save data from this profile back to the game's normal locations.");
+                    if import_btn.clicked() {
+                        let start = match gamesave_mappings(name, h, &self.options) {
+                            Ok(mappings) => mappings
+                                .into_iter()
+                                .map(|(native, _)| native)
+                                .find(|p| p.exists())
+                                .unwrap_or(PATH_HOME.clone()),
+                            Err(err) => {
+                                msg("Error", &format!("Couldn't find save locations: {err}"));
+                                return;
+                            }
+                        };
+                        if let Some(src) = rfd::FileDialog::new()
+                            .set_title("Select save data to import")
+                            .set_directory(start)
+                            .pick_folder()
+                        {
+                            match import_gamesave(name, h, &self.options, &src) {
+                                Ok(dest) => msg(
+                                    "Save Imported",
+                                    &format!("Imported save data to {}", dest.display()),
+                                ),
+                                Err(err) => msg("Error", &format!("Couldn't import save: {err}")),
+                            }
+                        }
+                    }
+                    if export_btn.clicked() {
+                        let start = PATH_PARTY.join("profiles").join(name).join("saves").join(&h.uid);
+                        if !start.exists() {
+                            msg("Error", &format!("{name} has no save data for this game."));
+                            return;
+                        }
+                        if let Some(src) = rfd::FileDialog::new()
+                            .set_title("Select save data to export")
+                            .set_directory(start)
+                            .pick_folder()
+                        {
+                            match export_gamesave(name, h, &self.options, &src) {
+                                Ok(dest) => msg(
+                                    "Save Exported",
+                                    &format!("Exported save data to {}", dest.display()),
+                                ),
+                                Err(err) => msg("Error", &format!("Couldn't export save: {err}")),
+                            }
+                        }
+                    }
+                });
+            });
+        }
+
+        let uid = cur_game!(self).uid().to_string();
+        egui::CollapsingHeader::new("Advanced").show(ui, |ui| {
+            self.display_game_overrides(ui, &uid);
+        });
+
+        egui::CollapsingHeader::new("Presets").show(ui, |ui| {
+            self.display_game_presets(ui, &uid);
+        });
+
         if let HandlerRef(h) = cur_game!(self) {
             egui::ScrollArea::horizontal()
                 .max_width(f32::INFINITY)
@@ -168,6 +343,555 @@ impl PartyApp {
         }
     }
 
+    pub fn display_game_presets(&mut self, ui: &mut Ui, uid: &str) {
+        let presets: Vec<Preset> = self
+            .presets
+            .iter()
+            .filter(|p| p.game == uid)
+            .cloned()
+            .collect();
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("Add to Applications Menu")
+                .on_hover_text("Creates a desktop entry that opens PartyDeck on this game's page.")
+                .clicked()
+            {
+                match create_game_desktop_entry(cur_game!(self)) {
+                    Ok(path) => msg(
+                        "Added to Applications Menu",
+                        &format!("Created {}", path.display()),
+                    ),
+                    Err(err) => msg("Error", &format!("Couldn't create desktop entry: {err}")),
+                }
+            }
+            if ui
+                .button("🔗 Copy Link")
+                .on_hover_text(launch_url(uid, None))
+                .clicked()
+            {
+                ui.ctx().copy_text(launch_url(uid, None));
+            }
+        });
+
+        if presets.is_empty() {
+            ui.label("Save a preset from the Play page to start this game with the same players in one click.");
+            return;
+        }
+
+        for preset in presets {
+            ui.horizontal(|ui| {
+                ui.label(&preset.name);
+                let players: Vec<String> = preset
+                    .profiles
+                    .iter()
+                    .map(|p| match p.is_empty() {
+                        true => "Guest".to_string(),
+                        false => p.clone(),
+                    })
+                    .collect();
+                ui.label(RichText::new(players.join(", ")).weak());
+                if ui.button("▶ Launch").clicked() {
+                    self.launch_preset(&preset);
+                }
+                if ui
+                    .button("Add to Steam")
+                    .on_hover_text("Adds a non-Steam game to your Steam library that launches this preset, using this game's artwork. Close Steam first; it shows up the next time Steam starts.")
+                    .clicked()
+                {
+                    match add_steam_shortcut(&preset, cur_game!(self)) {
+                        Ok(name) => msg(
+                            "Added to Steam",
+                            &format!("Added \"{name}\" to your Steam library. It will show up the next time Steam starts."),
+                        ),
+                        Err(err) => msg("Error", &format!("Couldn't add Steam shortcut: {err}")),
+                    }
+                }
+                if ui
+                    .button("Add to Applications Menu")
+                    .on_hover_text("Creates a desktop entry that launches this preset.")
+                    .clicked()
+                {
+                    match create_preset_desktop_entry(&preset, cur_game!(self)) {
+                        Ok(path) => msg("Added to Applications Menu", &format!("Created {}", path.display())),
+                        Err(err) => msg("Error", &format!("Couldn't create desktop entry: {err}")),
+                    }
+                }
+                let url = launch_url(uid, Some(&preset.name));
+                if ui.button("🔗").on_hover_text(format!("Copy link: {url}")).clicked() {
+                    ui.ctx().copy_text(url);
+                }
+                if ui.button("🗑").clicked()
+                    && yesno("Remove Preset", &format!("Remove preset \"{}\"?", preset.name))
+                {
+                    match remove_preset(&preset.game, &preset.name) {
+                        Ok(presets) => self.presets = presets,
+                        Err(err) => msg("Error", &format!("Couldn't remove preset: {err}")),
+                    }
+                }
+            });
+        }
+    }
+
+    pub fn display_game_overrides(&mut self, ui: &mut Ui, uid: &str) {
+        if self.overrides_uid != uid {
+            self.overrides = load_overrides(uid);
+            self.overrides_env = self.overrides.env.join("\n");
+            self.overrides_args = self.overrides.args.join(" ");
+            self.overrides_uid = uid.to_string();
+        }
+
+        ui.label("Settings changed here only apply to this game and take priority over the global settings.");
+
+        ui.horizontal(|ui| {
+            let mut enabled = self.overrides.proton_version.is_some();
+            ui.checkbox(&mut enabled, "Proton version");
+            match (enabled, &mut self.overrides.proton_version) {
+                (true, None) => self.overrides.proton_version = Some(self.options.proton_version.clone()),
+                (false, Some(_)) => self.overrides.proton_version = None,
+                _ => {}
+            }
+            if let Some(proton_version) = &mut self.overrides.proton_version {
+                proton_picker(ui, "override_proton_version", proton_version, &self.proton_installs);
+                ui.add(egui::TextEdit::singleline(proton_version).hint_text("GE-Proton"));
+            }
+        })
+        .response
+        .on_hover_text("Proton version used for this game instead of the global one. Can be a path or a name, e.g. \"GE-Proton\".");
+
+        ui.horizontal(|ui| {
+            let mut enabled = self.overrides.render_scale.is_some();
+            ui.checkbox(&mut enabled, "Resolution scale");
+            match (enabled, self.overrides.render_scale) {
+                (true, None) => self.overrides.render_scale = Some(self.options.render_scale),
+                (false, Some(_)) => self.overrides.render_scale = None,
+                _ => {}
+            }
+            if let Some(render_scale) = &mut self.overrides.render_scale {
+                ui.add(egui::Slider::new(render_scale, 35..=200));
+            }
+        });
+
+        override_combo(
+            ui,
+            "Vertical split for 2 players",
+            &mut self.overrides.vertical_two_player,
+            self.options.vertical_two_player,
+        );
+        override_combo(
+            ui,
+            "Force Steam Runtime SDL2",
+            &mut self.overrides.force_sdl,
+            self.options.force_sdl,
+        );
+        override_combo(
+            ui,
+            "Use SDL backend for Gamescope",
+            &mut self.overrides.gamescope_sdl_backend,
+            self.options.gamescope_sdl_backend,
+        );
+
+        ui.label("Environment variables (one KEY=VALUE per line)");
+        ui.add(
+            egui::TextEdit::multiline(&mut self.overrides_env)
+                .desired_rows(2)
+                .hint_text("DXVK_HUD=fps"),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Extra arguments");
+            ui.add(egui::TextEdit::singleline(&mut self.overrides_args).hint_text("-windowed"));
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Save Overrides").clicked() {
+                self.overrides.env = self
+                    .overrides_env
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                self.overrides.args = self
+                    .overrides_args
+                    .split_whitespace()
+                    .map(|arg| arg.to_string())
+                    .collect();
+                if let Some(bad) = self.overrides.env.iter().find(|var| !var.contains('=')) {
+                    msg("Error", &format!("Invalid environment variable: {bad}"));
+                } else if let Err(e) = save_overrides(uid, &self.overrides) {
+                    msg("Error", &format!("Couldn't save overrides: {}", e));
+                }
+            }
+            if ui.button("Reset").clicked() {
+                self.overrides = GameOverrides::default();
+                self.overrides_env.clear();
+                self.overrides_args.clear();
+                if let Err(e) = save_overrides(uid, &self.overrides) {
+                    msg("Error", &format!("Couldn't save overrides: {}", e));
+                }
+            }
+        });
+    }
+
+    pub fn display_executable_properties(&mut self, ui: &mut Ui, entry: &ExecutableEntry) {
+        if self.exe_edit.uid != entry.uid {
+            self.exe_edit = entry.clone();
+            self.exe_edit_env = entry
+                .env
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.exe_edit_args = entry.args.join(" ");
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.add(egui::TextEdit::singleline(&mut self.exe_edit.name).hint_text(entry.filename()));
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("Change Icon...")
+                .on_hover_text("Pick an image, or a Windows executable to use its icon.")
+                .clicked()
+                && let Some(src) = rfd::FileDialog::new()
+                    .set_title("Select icon")
+                    .set_directory(entry.root())
+                    .add_filter(
+                        "Images and executables",
+                        &["png", "jpg", "jpeg", "ico", "exe"],
+                    )
+                    .pick_file()
+            {
+                match set_executable_icon(entry, &src) {
+                    Ok(()) => ui
+                        .ctx()
+                        .forget_image(&format!("file://{}", entry.path_icon().display())),
+                    Err(err) => msg("Error", &format!("Couldn't set icon: {err}")),
+                }
+            }
+            if entry.path_icon().exists()
+                && ui.button("Reset Icon").clicked()
+                && let Err(err) = std::fs::remove_file(entry.path_icon())
+            {
+                msg("Error", &format!("Couldn't reset icon: {err}"));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Working directory");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.exe_edit.workdir)
+                    .hint_text(entry.root().to_string_lossy()),
+            );
+            if ui.button("Browse...").clicked()
+                && let Some(dir) = rfd::FileDialog::new()
+                    .set_title("Select working directory")
+                    .set_directory(entry.root())
+                    .pick_folder()
+            {
+                self.exe_edit.workdir = dir.to_string_lossy().to_string();
+            }
+        })
+        .response
+        .on_hover_text("Folder the game runs from. It has to contain the executable, and paths the game writes to are relative to it.");
+
+        if !entry.is_win() {
+            ui.horizontal(|ui| {
+                ui.label("Steam Runtime");
+                egui::ComboBox::from_id_salt("exe_edit_runtime")
+                    .selected_text(match self.exe_edit.runtime.as_str() {
+                        "" => "None",
+                        runtime => runtime,
+                    })
+                    .show_ui(ui, |ui| {
+                        for (runtime, label) in
+                            [("", "None"), ("scout", "scout"), ("soldier", "soldier")]
+                        {
+                            ui.selectable_value(
+                                &mut self.exe_edit.runtime,
+                                runtime.to_string(),
+                                label,
+                            );
+                        }
+                    });
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Separate per profile:");
+            if entry.is_win() {
+                ui.checkbox(&mut self.exe_edit.unique_appdata, "AppData");
+                ui.checkbox(&mut self.exe_edit.unique_documents, "Documents");
+            } else {
+                ui.checkbox(&mut self.exe_edit.unique_localshare, "~/.local/share");
+                ui.checkbox(&mut self.exe_edit.unique_config, "~/.config");
+            }
+        })
+        .response
+        .on_hover_text("Gives each profile its own copy of these folders, so players don't share save data and settings.");
+
+        ui.label("Environment variables (one KEY=VALUE per line)");
+        ui.add(
+            egui::TextEdit::multiline(&mut self.exe_edit_env)
+                .desired_rows(2)
+                .hint_text("DXVK_HUD=fps"),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Arguments");
+            ui.add(egui::TextEdit::singleline(&mut self.exe_edit_args).hint_text("-windowed"));
+        })
+        .response
+        .on_hover_text("Arguments can use template variables such as $PLAYER_INDEX or $PROFILE.");
+
+        if ui.button("Save Properties").clicked() {
+            let mut edited = self.exe_edit.clone();
+            edited.args = self
+                .exe_edit_args
+                .split_whitespace()
+                .map(|arg| arg.to_string())
+                .collect();
+            edited.env.clear();
+            for line in self
+                .exe_edit_env
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+            {
+                let Some((key, value)) = line.split_once('=') else {
+                    msg("Error", &format!("Invalid environment variable: {line}"));
+                    return;
+                };
+                edited.env.insert(key.to_string(), value.to_string());
+            }
+            if let Err(err) = edited.validate() {
+                msg("Error", &format!("Couldn't save properties: {err}"));
+                return;
+            }
+
+            let saved = PathsStore::update(|store| {
+                for e in store.executables.iter_mut().filter(|e| e.uid == edited.uid) {
+                    *e = edited.clone();
+                }
+            });
+            match saved {
+                Ok(()) => {
+                    self.games[self.selected_game] = Executable(edited.clone());
+                    self.exe_edit = edited;
+                }
+                Err(err) => msg("Error", &format!("Couldn't save properties: {err}")),
+            }
+        }
+    }
+
+    pub fn display_page_catalogue(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Handler Catalogue");
+            if ui.button("🔄 Refresh").clicked() {
+                let url = self.options.handler_index_url.clone();
+                let catalogue = self.catalogue.clone();
+                self.spawn_task("Fetching handler index...", move || {
+                    match fetch_catalogue(&url) {
+                        Ok(entries) => *catalogue.lock().unwrap() = entries,
+                        Err(err) => {
+                            println!("Couldn't fetch handler index: {}", err);
+                            msg("Error", &format!("Couldn't fetch handler index: {}", err));
+                        }
+                    }
+                });
+            }
+        });
+        ui.separator();
+
+        if self.options.handler_index_url.is_empty() {
+            ui.label("No handler index set. Enter an index URL or file path in Settings.");
+            return;
+        }
+
+        let entries = self.catalogue.lock().unwrap().clone();
+        if entries.is_empty() {
+            ui.label("No handlers loaded. Press Refresh to fetch the handler index.");
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                for entry in entries {
+                    let installed = self.games.iter().find_map(|game| match game {
+                        HandlerRef(h) if h.uid == entry.uid => Some(h.version.clone()),
+                        _ => None,
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(entry.display()).strong());
+                        ui.label(format!("v{}", entry.version));
+                        ui.add(egui::Separator::default().vertical());
+                        let action = match &installed {
+                            None => Some("Install"),
+                            Some(v) if is_newer_version(&entry.version, v) => {
+                                ui.label(format!("Installed: v{v}"));
+                                Some("Update")
+                            }
+                            Some(_) => {
+                                ui.label("✔ Installed");
+                                None
+                            }
+                        };
+                        if let Some(action) = action
+                            && ui.button(action).clicked()
+                        {
+                            self.rescan_games_after_task = true;
+                            self.spawn_task(
+                                &format!("Installing {}...", entry.display()),
+                                move || {
+                                    if let Err(err) = install_from_catalogue(&entry) {
+                                        println!("Couldn't install handler: {}", err);
+                                        msg("Error", &format!("Couldn't install handler: {}", err));
+                                    }
+                                },
+                            );
+                        }
+                    });
+                }
+            });
+    }
+
+    pub fn scan_steam_library(&mut self) {
+        let steam_apps = self.steam_apps.clone();
+        self.spawn_task("Scanning Steam libraries...", move || {
+            *steam_apps.lock().unwrap() = scan_steam_apps();
+        });
+    }
+
+    pub fn display_page_steam_import(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Import from Steam");
+            if ui.button("🔄 Refresh").clicked() {
+                self.scan_steam_library();
+            }
+        });
+        ui.separator();
+
+        let steam_apps = self.steam_apps.clone();
+        let mut apps = steam_apps.lock().unwrap();
+        if apps.is_empty() {
+            ui.label("No installed Steam games found.");
+            return;
+        }
+
+        let mut import = false;
+        ui.horizontal(|ui| {
+            let selected = apps.iter().filter(|app| app.selected).count();
+            ui.add_enabled_ui(selected > 0, |ui| {
+                import = ui.button(format!("Import {selected} Selected")).clicked();
+            });
+        });
+
+        egui::ScrollArea::vertical()
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                egui::Grid::new("steam_apps")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for app in apps.iter_mut() {
+                            let handler = self.games.iter().find_map(|game| match game {
+                                HandlerRef(h) if h.steam_appid == Some(app.appid.to_string()) => {
+                                    Some(h.display().to_string())
+                                }
+                                _ => None,
+                            });
+                            let added = self.games.iter().any(|game| match game {
+                                Executable(e) => {
+                                    std::path::Path::new(&e.path).starts_with(&app.path_root)
+                                }
+                                _ => false,
+                            });
+
+                            match (handler, added) {
+                                (Some(name), _) => {
+                                    ui.label("✔ Handler")
+                                        .on_hover_text(format!("Installed handler: {name}"));
+                                }
+                                (None, true) => {
+                                    ui.label("✔ Added");
+                                }
+                                (None, false) => {
+                                    ui.add_enabled(
+                                        app.exe.is_some(),
+                                        egui::Checkbox::without_text(&mut app.selected),
+                                    );
+                                }
+                            }
+                            ui.label(RichText::new(&app.name).strong())
+                                .on_hover_text(format!(
+                                    "App {}: {}",
+                                    app.appid,
+                                    app.path_root.display()
+                                ));
+
+                            ui.horizontal(|ui| {
+                                match &app.exe {
+                                    Some(exe) => ui.label(
+                                        exe.strip_prefix(&app.path_root)
+                                            .unwrap_or(exe)
+                                            .display()
+                                            .to_string(),
+                                    ),
+                                    None => ui.label("No executable found"),
+                                };
+                                if ui.button("Change...").clicked()
+                                    && let Some(exe) = rfd::FileDialog::new()
+                                        .set_title(format!("Select the executable of {}", app.name))
+                                        .set_directory(&app.path_root)
+                                        .pick_file()
+                                {
+                                    app.exe = Some(exe);
+                                    app.selected = true;
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if !import {
+            return;
+        }
+        let mut errors = Vec::new();
+        for app in apps.iter_mut().filter(|app| app.selected) {
+            app.selected = false;
+            let Some(exe) = &app.exe else {
+                continue;
+            };
+            let entry = ExecutableEntry {
+                name: app.name.clone(),
+                workdir: app.path_root.to_string_lossy().to_string(),
+                ..ExecutableEntry::new(&exe.to_string_lossy())
+            };
+            match add_executable(&entry) {
+                Ok(true) => {
+                    let icon =
+                        steam_app_icon(app.appid).or_else(|| entry.is_win().then(|| exe.clone()));
+                    if let Some(icon) = icon
+                        && let Err(err) = set_executable_icon(&entry, &icon)
+                    {
+                        println!("Couldn't set icon for {}: {err}", app.name);
+                    }
+                }
+                Ok(false) => {}
+                Err(err) => errors.push(format!("{}: {err}", app.name)),
+            }
+        }
+        if !errors.is_empty() {
+            msg(
+                "Error",
+                &format!("Couldn't import some games:\n{}", errors.join("\n")),
+            );
+        }
+        self.games = scan_all_games();
+    }
+
     pub fn display_page_instances(&mut self, ui: &mut Ui) {
         ui.heading("Instances");
         ui.separator();
@@ -286,6 +1010,19 @@ impl PartyApp {
                 if ui.button("Start").clicked() {
                     self.prepare_game_launch();
                 }
+                ui.add(egui::Separator::default().vertical());
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.preset_name)
+                        .hint_text("Preset name")
+                        .desired_width(120.0),
+                );
+                if ui
+                    .button("💾 Save Preset")
+                    .on_hover_text("Saves these players, their profiles and device types as a preset, which can be launched from the game's page, a Steam shortcut or `partydeck launch <game uid> --preset <name>`.")
+                    .clicked()
+                {
+                    self.save_instances_preset();
+                }
             });
         }
     }
@@ -344,14 +1081,37 @@ impl PartyApp {
             }
         });
 
+        ui.horizontal(|ui| {
+            let index_url_label = ui.label("Handler index");
+            let index_url_editbox = ui.add(
+                egui::TextEdit::singleline(&mut self.options.handler_index_url)
+                    .hint_text("https://example.com/index.json or /path/to/index.json"),
+            );
+            if index_url_label.hovered() || index_url_editbox.hovered() {
+                self.infotext = "URL or local path of the handler index used by the handler catalogue. The index is a JSON list of handlers with their uid, name, version, download URL and sha256 checksum. Download URLs can be relative to the index, so a local folder or mirror can be used.".to_string();
+            }
+        });
+
+        let per_game_prefix_check = ui.checkbox(
+            &mut self.options.per_game_prefix,
+            "Use a separate Proton prefix for each game",
+        );
+        if per_game_prefix_check.hovered() {
+            self.infotext = "Gives every Windows game its own Wine prefix instead of sharing one between all games. Useful if games need conflicting winetricks, DLL overrides or Wine versions. Handlers can also request their own prefix regardless of this setting. Note that switching this on starts games from a fresh prefix.".to_string();
+        }
+
         ui.horizontal(|ui| {
         let proton_ver_label = ui.label("Proton version");
+        let proton_ver_picker = proton_picker(ui, "proton_version", &mut self.options.proton_version, &self.proton_installs);
         let proton_ver_editbox = ui.add(
             egui::TextEdit::singleline(&mut self.options.proton_version)
                 .hint_text("GE-Proton"),
         );
-        if proton_ver_label.hovered() || proton_ver_editbox.hovered() {
-            self.infotext = "Specify a Proton version. This can be a path, e.g. \"/path/to/proton\" or just a name, e.g. \"GE-Proton\" for the latest version of Proton-GE. If left blank, this will default to \"GE-Proton\". If unsure, leave this blank.".to_string();
+        if ui.button("🔄").clicked() {
+            self.proton_installs = scan_proton_installs();
+        }
+        if proton_ver_label.hovered() || proton_ver_picker.hovered() || proton_ver_editbox.hovered() {
+            self.infotext = "Specify a Proton version. Pick one of the installed Proton builds found in Steam's libraries, compatibilitytools.d or UMU's directories, or type the path to a Proton folder or its proton script, e.g. \"/path/to/proton\", or just a name, e.g. \"GE-Proton\" for the latest version of Proton-GE. If left blank, this will default to \"GE-Proton\". If unsure, leave this blank.".to_string();
         }
         });
 
@@ -376,7 +1136,7 @@ impl PartyApp {
 
         ui.horizontal(|ui| {
         if ui.button("Erase Proton Prefix").clicked() {
-            if yesno("Erase Prefix?", "This will erase the Wine prefix used by PartyDeck. This shouldn't erase profile/game-specific data, but exercise caution. Are you sure?") && PATH_PARTY.join("gamesyms").exists() {
+            if yesno("Erase Prefix?", "This will erase all Wine prefixes used by PartyDeck, including per-game prefixes. This shouldn't erase profile/game-specific data, but exercise caution. Are you sure?") && PATH_PARTY.join("gamesyms").exists() {
                 if let Err(err) = std::fs::remove_dir_all(PATH_PARTY.join("pfx")) {
                     msg("Error", &format!("Couldn't erase pfx data: {}", err));
                 }
@@ -405,6 +1165,16 @@ impl PartyApp {
         });
 
         ui.horizontal(|ui| {
+            if ui
+                .button("Register partydeck:// Links")
+                .on_hover_text("Makes partydeck://launch/<game>?preset=<name> links open in PartyDeck, so launchers and scripts can start sessions. Done automatically when adding a game or preset to the applications menu.")
+                .clicked()
+            {
+                match register_url_handler() {
+                    Ok(()) => msg("Links Registered", "partydeck:// links now open in PartyDeck."),
+                    Err(err) => msg("Error", &format!("Couldn't register partydeck:// links: {err}")),
+                }
+            }
             if ui.button("Open PartyDeck Data Folder").clicked() {
                 if let Err(_) = std::process::Command::new("sh")
                     .arg("-c")
@@ -414,18 +1184,104 @@ impl PartyApp {
                     msg("Error", "Couldn't open PartyDeck Data Folder!");
                 }
             }
-            if ui.button("Edit game paths").clicked() {
-                if let Err(_) = std::process::Command::new("sh")
-                    .arg("-c")
-                    .arg(format!("xdg-open {}/paths.json", PATH_PARTY.display(),))
-                    .status()
-                {
-                    msg("Error", "Couldn't open paths.json!");
-                }
-            }
         });
     }
 
+    pub fn display_settings_locations(&mut self, ui: &mut Ui) {
+        if self.game_locations.is_empty() {
+            ui.label("No game locations saved. Locations are saved when a game is found or located for the first time.");
+            return;
+        }
+
+        let mut changed = false;
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 40.0)
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                egui::Grid::new("game_locations")
+                    .striped(true)
+                    .num_columns(6)
+                    .show(ui, |ui| {
+                        for (uid, path) in &self.game_locations {
+                            let handler = self.games.iter().find_map(|game| match game {
+                                HandlerRef(h) if h.uid == *uid => Some(h),
+                                _ => None,
+                            });
+                            let root = std::path::Path::new(path);
+
+                            let name = self
+                                .games
+                                .iter()
+                                .find(|game| game.uid() == uid)
+                                .map(|game| game.name())
+                                .unwrap_or(uid.clone());
+                            ui.label(&name);
+                            ui.label(path);
+                            let (status, detail) = match handler {
+                                _ if !root.is_dir() => ("✖ Missing", "This folder doesn't exist anymore.".to_string()),
+                                Some(h) if !h.is_installed_in(root) => (
+                                    "⚠ Stale",
+                                    format!("{} wasn't found in this folder. The game will be looked for again when it's launched.", h.exec),
+                                ),
+                                _ => ("✔ Found", "This location is valid.".to_string()),
+                            };
+                            ui.label(status).on_hover_text(detail);
+
+                            if ui.button("Re-locate").clicked()
+                                && let Some(folder) = rfd::FileDialog::new()
+                                    .set_title(format!("Locate folder for {name}"))
+                                    .set_directory(&*PATH_HOME)
+                                    .pick_folder()
+                            {
+                                if let Err(err) = add_path(uid, &folder.to_string_lossy().to_string()) {
+                                    msg("Error", &format!("Couldn't save location: {}", err));
+                                }
+                                changed = true;
+                            }
+
+                            match handler {
+                                Some(h) => {
+                                    let btn = ui.button("Auto-detect");
+                                    if btn.hovered() {
+                                        self.infotext = "Forgets this location and looks for the game in Steam and other launchers' libraries again.".to_string();
+                                    }
+                                    if btn.clicked() {
+                                        if let Err(err) = remove_path(uid) {
+                                            msg("Error", &format!("Couldn't reset location: {}", err));
+                                        }
+                                        match find_rootpath_handler(h) {
+                                            Some(Ok(_)) => {}
+                                            Some(Err(err)) => msg("Error", &format!("Couldn't save location: {}", err)),
+                                            None => msg("Not Found", &format!("Couldn't find {} automatically. You'll be asked for its folder when launching it.", h.display())),
+                                        }
+                                        changed = true;
+                                    }
+                                }
+                                None => {
+                                    ui.label("");
+                                }
+                            }
+
+                            let btn = ui.button("Remove");
+                            if btn.hovered() {
+                                self.infotext = "Forgets this location. PartyDeck will look for the game again or ask for its folder the next time it's needed.".to_string();
+                            }
+                            if btn.clicked() {
+                                if let Err(err) = remove_path(uid) {
+                                    msg("Error", &format!("Couldn't remove location: {}", err));
+                                }
+                                changed = true;
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if changed {
+            self.game_locations = saved_paths();
+        }
+    }
+
     pub fn display_settings_gamescope(&mut self, ui: &mut Ui) {
         let render_scale_slider = ui.add(
             egui::Slider::new(&mut self.options.render_scale, 35..=200)
@@ -451,3 +1307,56 @@ impl PartyApp {
         }
     }
 }
+
+// Tri-state dropdown for a per-game boolean override: default (global setting), on or off
+fn override_combo(
+    ui: &mut Ui,
+    label: &str,
+    value: &mut Option<bool>,
+    global: bool,
+) -> egui::Response {
+    let text = |v: Option<bool>| match v {
+        None => format!("Default ({})", if global { "On" } else { "Off" }),
+        Some(true) => "On".to_string(),
+        Some(false) => "Off".to_string(),
+    };
+    egui::ComboBox::from_label(label)
+        .selected_text(text(*value))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, text(None));
+            ui.selectable_value(value, Some(true), text(Some(true)));
+            ui.selectable_value(value, Some(false), text(Some(false)));
+        })
+        .response
+}
+
+// Dropdown of installed Proton builds; picking one stores its path
+fn proton_picker(
+    ui: &mut Ui,
+    id: &str,
+    proton_version: &mut String,
+    installs: &[ProtonInstall],
+) -> egui::Response {
+    let selected = match installs
+        .iter()
+        .find(|p| p.path.to_string_lossy() == proton_version.as_str())
+    {
+        Some(install) => install.display(),
+        None if proton_version.is_empty() => "Default (GE-Proton)".to_string(),
+        None => proton_version.clone(),
+    };
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(proton_version, String::new(), "Default (GE-Proton)");
+            for install in installs {
+                ui.selectable_value(
+                    proton_version,
+                    install.path.to_string_lossy().to_string(),
+                    install.display(),
+                )
+                .on_hover_text(install.path.display().to_string());
+            }
+        })
+        .response
+}
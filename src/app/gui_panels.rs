@@ -1,12 +1,18 @@
 use super::app::{MenuPage, PartyApp};
 use crate::game::{Game::*, *};
+use crate::handler::export_handler;
 use crate::input::*;
+use crate::library::*;
+use crate::lint::lint_handler_dir;
 use crate::paths::*;
 use crate::util::*;
 
 use eframe::egui::RichText;
 use eframe::egui::{self, Ui};
 
+// How many games the Recently Played section shows
+const RECENT_GAMES: usize = 5;
+
 macro_rules! cur_game {
     ($self:expr) => {
         &$self.games[$self.selected_game]
@@ -80,9 +86,19 @@ impl PartyApp {
                 if ui.button("🔄").clicked() {
                     self.games = crate::game::scan_all_games();
                 }
+                if ui.button("🌐").on_hover_text("Browse handlers").clicked() {
+                    self.cur_page = MenuPage::Catalogue;
+                }
+                if ui.button("🎮").on_hover_text("Import from Steam").clicked() {
+                    self.cur_page = MenuPage::SteamImport;
+                    if self.steam_apps.lock().unwrap().is_empty() {
+                        self.scan_steam_library();
+                    }
+                }
             });
         });
         ui.separator();
+        self.panel_left_game_filters(ui);
         egui::ScrollArea::vertical().show(ui, |ui| {
             self.panel_left_game_list(ui);
         });
@@ -98,14 +114,18 @@ impl PartyApp {
                 match self.cur_page {
                     MenuPage::Game => {
                         match cur_game!(self){
-                            Game::Executable { path, .. } =>
-                                self.infotext = format!("{}", path.display()),
+                            Game::Executable(e) =>
+                                self.infotext = e.path.clone(),
                             Game::HandlerRef(h) =>
                                 self.infotext = h.info.to_owned(),
                         }
                     }
                     MenuPage::Profiles =>
                         self.infotext = "Create profiles to persistently store game save data, settings, and stats.".to_string(),
+                    MenuPage::Catalogue =>
+                        self.infotext = "Browse and install handlers from the handler index set in settings. Installed handlers with a newer version in the index can be updated from here.".to_string(),
+                    MenuPage::SteamImport =>
+                        self.infotext = "Games installed through Steam. Games with an installed handler are launched through it; the others can be added as executables. Check the guessed executable before importing.".to_string(),
                     _ => {}
                 }
                 egui::ScrollArea::vertical().show(ui, |ui| {
@@ -143,66 +163,211 @@ impl PartyApp {
         }
     }
 
+    // Search box and filters for the games list. Filters are buttons, so they can be reached with a gamepad.
+    fn panel_left_game_filters(&mut self, ui: &mut Ui) {
+        ui.add(egui::TextEdit::singleline(&mut self.game_filter.search).hint_text("🔍 Search"));
+        let title = match self.game_filter.is_active() {
+            true => "Filters (active)",
+            false => "Filters",
+        };
+        egui::CollapsingHeader::new(title)
+            .id_salt("game_filters")
+            .show(ui, |ui| {
+                let filter = &mut self.game_filter;
+                ui.horizontal_wrapped(|ui| {
+                    ui.selectable_value(&mut filter.platform, PlatformFilter::All, "All");
+                    ui.selectable_value(&mut filter.platform, PlatformFilter::Native, "🐧 Native");
+                    ui.selectable_value(&mut filter.platform, PlatformFilter::Proton, " Proton");
+                });
+                ui.horizontal_wrapped(|ui| {
+                    ui.selectable_value(&mut filter.kind, KindFilter::All, "All");
+                    ui.selectable_value(&mut filter.kind, KindFilter::Handlers, "Handlers");
+                    ui.selectable_value(&mut filter.kind, KindFilter::Executables, "Executables");
+                });
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Players:");
+                    ui.selectable_value(&mut filter.min_players, 0, "Any");
+                    for players in 2..=4 {
+                        ui.selectable_value(
+                            &mut filter.min_players,
+                            players,
+                            format!("{players}+"),
+                        );
+                    }
+                });
+            });
+        ui.separator();
+    }
+
     pub fn panel_left_game_list(&mut self, ui: &mut Ui) {
         let mut refresh_games = false;
+        let mut edit_game = None;
 
-        for (i, game) in self.games.iter().enumerate() {
-            ui.horizontal(|ui| {
-                ui.add(
-                    egui::Image::new(game.icon())
-                        .max_width(16.0)
-                        .corner_radius(2),
-                );
-                let btn = ui.selectable_value(&mut self.selected_game, i, game.name());
-                if btn.has_focus() {
-                    btn.scroll_to_me(None);
-                }
-                if btn.clicked() {
-                    self.cur_page = MenuPage::Game;
-                };
+        let visible: Vec<usize> = (0..self.games.len())
+            .filter(|&i| self.game_filter.matches(&self.games[i]))
+            .collect();
+        if visible.is_empty() && !self.games.is_empty() {
+            ui.label("No games match the search and filters.");
+        }
+        let favorites: Vec<usize> = visible
+            .iter()
+            .copied()
+            .filter(|&i| self.library.is_favorite(self.games[i].uid()))
+            .collect();
+        let mut recent: Vec<usize> = visible
+            .iter()
+            .copied()
+            .filter(|&i| self.library.stats(self.games[i].uid()).last_played > 0)
+            .collect();
+        recent.sort_by_key(|&i| {
+            std::cmp::Reverse(self.library.stats(self.games[i].uid()).last_played)
+        });
+        recent.truncate(RECENT_GAMES);
 
-                let popup_id = ui.make_persistent_id(format!("gamectx{}", i));
-
-                egui::popup::popup_below_widget(
-                    ui,
-                    popup_id,
-                    &btn,
-                    egui::popup::PopupCloseBehavior::CloseOnClick,
-                    |ui| {
-                        if ui.button("Remove").clicked() {
-                            if yesno(
-                                "Remove game?",
-                                &format!("Are you sure you want to remove {}?", game.name()),
-                            ) {
-                                if let Err(err) = remove_game(&self.games[i]) {
-                                    println!("Failed to remove game: {}", err);
-                                    msg("Error", &format!("Failed to remove game: {}", err));
-                                }
+        // Sections are only labelled when there's more than the full list
+        let labelled = !favorites.is_empty() || !recent.is_empty();
+        for (section, games) in [
+            ("★ Favorites", favorites),
+            ("🕑 Recently Played", recent),
+            ("All Games", visible),
+        ] {
+            if games.is_empty() {
+                continue;
+            }
+            if labelled {
+                ui.label(RichText::new(section).small().strong());
+            }
+            for i in games {
+                self.panel_left_game_entry(ui, i, section, &mut refresh_games, &mut edit_game);
+            }
+        }
+        // Hacky workaround to avoid borrowing conflicts from inside the loop
+        if refresh_games {
+            self.games = scan_all_games();
+        }
+        if let Some(i) = edit_game {
+            self.open_handler_editor(i);
+        }
+    }
+
+    fn panel_left_game_entry(
+        &mut self,
+        ui: &mut Ui,
+        i: usize,
+        section: &str,
+        refresh_games: &mut bool,
+        edit_game: &mut Option<usize>,
+    ) {
+        let game = &self.games[i];
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::Image::new(game.icon())
+                    .max_width(16.0)
+                    .corner_radius(2),
+            );
+            let btn = ui.selectable_value(&mut self.selected_game, i, game.name());
+            if btn.has_focus() {
+                btn.scroll_to_me(None);
+            }
+            if btn.clicked() {
+                self.cur_page = MenuPage::Game;
+            };
+
+            let popup_id = ui.make_persistent_id(format!("gamectx{section}{i}"));
+
+            egui::popup::popup_below_widget(
+                ui,
+                popup_id,
+                &btn,
+                egui::popup::PopupCloseBehavior::CloseOnClick,
+                |ui| {
+                    let favorite = self.library.is_favorite(game.uid());
+                    if ui.button(if favorite { "Unfavorite" } else { "Favorite" }).clicked() {
+                        match set_favorite(game.uid(), !favorite) {
+                            Ok(library) => self.library = library,
+                            Err(err) => msg("Error", &format!("Couldn't save favorite: {err}")),
+                        }
+                    }
+                    if ui.button("Remove").clicked() {
+                        if yesno(
+                            "Remove game?",
+                            &format!("Are you sure you want to remove {}?", game.name()),
+                        ) {
+                            if let Err(err) = remove_game(&self.games[i]) {
+                                println!("Failed to remove game: {}", err);
+                                msg("Error", &format!("Failed to remove game: {}", err));
                             }
-                            refresh_games = true;
                         }
-                        if let HandlerRef(h) = game {
-                            if ui.button("Open Handler Folder").clicked() {
-                                if let Err(_) = std::process::Command::new("sh")
-                                    .arg("-c")
-                                    .arg(format!("xdg-open {}", h.path_handler.display()))
-                                    .status()
-                                {
-                                    msg("Error", "Couldn't open handler folder!");
+                        *refresh_games = true;
+                    }
+                    if let Executable(_) = game
+                        && ui.button("Convert to Handler").clicked()
+                    {
+                        *edit_game = Some(i);
+                    }
+                    if let HandlerRef(h) = game {
+                        let pfx = h.path_prefix(&self.options);
+                        if h.win
+                            && pfx != PATH_PARTY.join("pfx")
+                            && ui.button("Erase Proton Prefix").clicked()
+                            && yesno(
+                                "Erase Prefix?",
+                                &format!("This will erase the Wine prefix used by {}. This shouldn't erase profile/game-specific data, but exercise caution. Are you sure?", game.name()),
+                            )
+                        {
+                            if let Err(err) = std::fs::remove_dir_all(&pfx) {
+                                msg("Error", &format!("Couldn't erase pfx data: {}", err));
+                            } else {
+                                msg("Data Erased", "Proton prefix data successfully erased.");
+                            }
+                        }
+                        if ui.button("Edit Handler").clicked() {
+                            *edit_game = Some(i);
+                        }
+                        if ui.button("Check Handler").clicked() {
+                            let diagnostics = lint_handler_dir(&h.path_handler);
+                            let report = match diagnostics.is_empty() {
+                                true => "No problems found.".to_string(),
+                                false => diagnostics
+                                    .iter()
+                                    .map(|d| d.to_string())
+                                    .collect::<Vec<String>>()
+                                    .join("\n"),
+                            };
+                            msg(&format!("Check Handler: {}", h.display()), &report);
+                        }
+                        if ui.button("Export Handler").clicked()
+                            && let Some(dest_dir) = rfd::FileDialog::new()
+                                .set_title("Select where to save the handler")
+                                .set_directory(&*PATH_HOME)
+                                .pick_folder()
+                        {
+                            match export_handler(h, &dest_dir) {
+                                Ok(path_pdh) => msg(
+                                    "Handler Exported",
+                                    &format!("Handler saved to {}", path_pdh.display()),
+                                ),
+                                Err(err) => {
+                                    msg("Error", &format!("Couldn't export handler: {}", err))
                                 }
                             }
                         }
-                    },
-                );
+                        if ui.button("Open Handler Folder").clicked() {
+                            if let Err(_) = std::process::Command::new("sh")
+                                .arg("-c")
+                                .arg(format!("xdg-open {}", h.path_handler.display()))
+                                .status()
+                            {
+                                msg("Error", "Couldn't open handler folder!");
+                            }
+                        }
+                    }
+                },
+            );
 
-                if btn.secondary_clicked() {
-                    ui.memory_mut(|mem| mem.toggle_popup(popup_id));
-                }
-            });
-        }
-        // Hacky workaround to avoid borrowing conflicts from inside the loop
-        if refresh_games {
-            self.games = scan_all_games();
-        }
+            if btn.secondary_clicked() {
+                ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+            }
+        });
     }
 }
@@ -0,0 +1,653 @@
+use super::app::{MenuPage, PartyApp};
+use crate::executable::ExecutableEntry;
+use crate::game::{Game, move_game_data, remove_game, scan_all_games};
+use crate::handler::{Handler, read_extended_json, read_handler_json};
+use crate::lint::{Diagnostic, Severity, lint_handler_json};
+use crate::paths::*;
+use crate::util::*;
+
+use eframe::egui::{self, RichText, Ui};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldKind {
+    Text,
+    Number,
+    Flag,
+    // A path relative to the game folder
+    GamePath,
+    // One value per line
+    List,
+    GamePathList,
+    // One KEY=VALUE per line
+    Env,
+}
+
+use FieldKind::*;
+
+// Every handler.json key the editor shows, with the explanation shown when hovering it
+const FIELDS: &[(&str, FieldKind, &str)] = &[
+    (
+        "handler.uid",
+        Text,
+        "Unique id of the handler, letters and numbers only. Profiles keep the game's saves under this id, so it can't be changed once the handler is saved.",
+    ),
+    ("handler.name", Text, "Name shown in the games list."),
+    ("handler.author", Text, "Who made the handler."),
+    (
+        "handler.version",
+        Text,
+        "Version of the handler, used by the handler catalogue to offer updates.",
+    ),
+    (
+        "handler.info",
+        Text,
+        "Notes about the game shown at the bottom of the game page, such as how to set up online play.",
+    ),
+    (
+        "game.exec",
+        GamePath,
+        "Executable to run, relative to the game folder. Can use template variables such as $PLAYER_INDEX.",
+    ),
+    (
+        "game.args",
+        List,
+        "Arguments passed to the executable, one per line. Can use template variables such as $WIDTH, $HEIGHT or $PROFILE. Lines without template variables are split on spaces, as older handlers put several arguments on one line.",
+    ),
+    (
+        "game.env",
+        Env,
+        "Environment variables set for each instance, one KEY=VALUE per line. Values can use template variables.",
+    ),
+    (
+        "game.win",
+        Flag,
+        "The game is a Windows game and is run through Proton.",
+    ),
+    (
+        "game.32bit",
+        Flag,
+        "The game is a 32-bit program. Picks the right Steam API library and Steam Runtime SDL2.",
+    ),
+    (
+        "game.max_players",
+        Number,
+        "Most players the game supports. Used by the games list filter; leave empty if there's no limit.",
+    ),
+    (
+        "game.runtime",
+        Text,
+        "Steam Runtime native games run in: \"scout\", \"soldier\" or nothing. Ignored for Windows games.",
+    ),
+    (
+        "game.symlink_dir",
+        Flag,
+        "Runs the game from a folder of symlinks to the game files, so files can be replaced or removed without touching the installed game.",
+    ),
+    (
+        "game.copy_instead_paths",
+        GamePathList,
+        "Files and folders copied into the symlinked folder instead of symlinked, for games that write to them. Needs game.symlink_dir.",
+    ),
+    (
+        "game.remove_paths",
+        GamePathList,
+        "Files and folders left out of the symlinked folder, such as launchers or DRM libraries. Needs game.symlink_dir.",
+    ),
+    (
+        "game.dll_overrides",
+        List,
+        "DLLs Wine loads from the game folder instead of its own, one name per line, e.g. \"winmm\".",
+    ),
+    (
+        "game.unique_prefix",
+        Flag,
+        "Gives the game its own Wine prefix instead of the shared one.",
+    ),
+    (
+        "game.prefix_setup",
+        List,
+        "Winetricks verbs or setup files applied to the Wine prefix, one per line.",
+    ),
+    (
+        "steam.api_path",
+        GamePath,
+        "Folder holding the game's Steam API library, relative to the game folder. Goldberg is installed there to replace Steam's networking. Use \".\" for the game folder itself.",
+    ),
+    (
+        "steam.appid",
+        Text,
+        "Steam app id of the game. Used to find the game in Steam libraries and by Goldberg.",
+    ),
+    (
+        "steam.gb_coldclient",
+        Flag,
+        "Uses Goldberg's ColdClient loader instead of replacing the Steam API library.",
+    ),
+    (
+        "gog.id",
+        Text,
+        "GOG product id, to find the game in Heroic's GOG library.",
+    ),
+    (
+        "epic.appname",
+        Text,
+        "Epic app name, to find the game in Heroic's or Legendary's Epic library.",
+    ),
+    (
+        "lutris.slug",
+        Text,
+        "Lutris game slug, to find the game in Lutris.",
+    ),
+    (
+        "bottles.program",
+        Text,
+        "Name or path of the program in a Bottles bottle, to find the game in Bottles.",
+    ),
+    (
+        "itch.id",
+        Text,
+        "itch.io game id, to find the game in the itch app.",
+    ),
+    (
+        "hooks.pre_launch",
+        Text,
+        "Script in the handler folder run once before the game starts, with $GAMEDIR and $PLAYER_COUNT set.",
+    ),
+    (
+        "hooks.per_instance_pre",
+        Text,
+        "Script in the handler folder run before each instance starts, with $GAMEDIR, $PLAYER_COUNT, $PROFILE and $PLAYER_INDEX set.",
+    ),
+    (
+        "hooks.post_session",
+        Text,
+        "Script in the handler folder run once all instances have closed, with $GAMEDIR and $PLAYER_COUNT set.",
+    ),
+    (
+        "hooks.background",
+        Flag,
+        "Don't wait for the pre-launch hooks to finish: they keep running alongside the game and are stopped when the session ends. Otherwise hooks are stopped after 60 seconds.",
+    ),
+    (
+        "profiles.unique_appdata",
+        Flag,
+        "Gives each profile its own AppData folder in the Wine prefix.",
+    ),
+    (
+        "profiles.unique_documents",
+        Flag,
+        "Gives each profile its own Documents folder in the Wine prefix.",
+    ),
+    (
+        "profiles.unique_localshare",
+        Flag,
+        "Gives each profile its own ~/.local/share.",
+    ),
+    (
+        "profiles.unique_config",
+        Flag,
+        "Gives each profile its own ~/.config.",
+    ),
+    (
+        "profiles.game_paths",
+        GamePathList,
+        "Folders in the game folder each profile gets its own copy of, such as save or config folders.",
+    ),
+];
+
+fn section_title(key: &str) -> &'static str {
+    match key.split('.').next().unwrap_or_default() {
+        "handler" => "Handler",
+        "game" => "Game",
+        "steam" => "Steam",
+        "hooks" => "Hooks",
+        "profiles" => "Profiles",
+        _ => "Other launchers",
+    }
+}
+
+// Draft of the handler open in the editor page
+#[derive(Default)]
+pub struct HandlerEditor {
+    // Folder of the handler being edited; None for a new handler, which is saved to handlers/<uid>
+    pub path_handler: Option<PathBuf>,
+    // Executable the handler is replacing, removed from the games list once the handler is saved
+    pub convert_from: Option<ExecutableEntry>,
+    // Game folder that paths are picked relative to
+    pub path_root: Option<PathBuf>,
+    values: HashMap<&'static str, String>,
+    flags: HashMap<&'static str, bool>,
+    // Keys the editor doesn't show, such as variants, kept for validation
+    extra: Map<String, Value>,
+    // The handler this one extends, as to_json would write it. Only keys that differ from it are saved.
+    base: Option<Value>,
+    diagnostics: Vec<Diagnostic>,
+    linted: Value,
+}
+
+impl HandlerEditor {
+    fn load(json: &Value) -> Self {
+        let mut editor = Self::default();
+        for (key, kind, _) in FIELDS {
+            let value = &json[*key];
+            if *kind == Flag {
+                editor
+                    .flags
+                    .insert(key, value.as_bool().unwrap_or_default());
+                continue;
+            }
+            let text = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) if n.as_u64() == Some(0) => String::new(),
+                Value::Number(n) => n.to_string(),
+                Value::Array(arr) => arr
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Value::Object(obj) => obj
+                    .iter()
+                    .map(|(k, v)| format!("{k}={}", v.as_str().unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            };
+            editor.values.insert(key, text);
+        }
+        if let Some(obj) = json.as_object() {
+            editor.extra = obj
+                .iter()
+                .filter(|(key, _)| !FIELDS.iter().any(|(k, _, _)| k == key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+        }
+        editor
+    }
+
+    // Opens an installed handler. Handlers using handler.extends show the values they inherit, but
+    // keys the editor doesn't show come from their own handler.json.
+    fn open(path_handler: &Path) -> Result<Self, Box<dyn Error>> {
+        let json_path = path_handler.join("handler.json");
+        let raw: Value = serde_json::from_reader(BufReader::new(File::open(&json_path)?))?;
+        let json = read_handler_json(&json_path, &mut Vec::new())?;
+        let base = read_extended_json(&raw, &mut Vec::new())?;
+        Ok(Self {
+            path_handler: Some(path_handler.to_path_buf()),
+            extra: Self::load(&raw).extra,
+            base: base.map(|base| Self::load(&base).to_json()),
+            ..Self::load(&json)
+        })
+    }
+
+    // The draft as handler.json, without the keys the editor doesn't show
+    fn to_json(&self) -> Value {
+        let mut obj = Map::new();
+        for (key, kind, _) in FIELDS {
+            let text = self.values.get(key).map(String::as_str).unwrap_or_default();
+            let lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+            let value = match kind {
+                Flag => Value::Bool(self.flags.get(key).copied().unwrap_or_default()),
+                Text | GamePath => Value::from(text.trim()),
+                Number => match text.trim().parse::<u64>() {
+                    Ok(n) => Value::from(n),
+                    Err(_) => Value::from(text.trim()),
+                },
+                List | GamePathList => Value::from(lines.collect::<Vec<_>>()),
+                Env => Value::Object(
+                    lines
+                        .map(|line| match line.split_once('=') {
+                            Some((k, v)) => (k.trim().to_string(), Value::from(v)),
+                            None => (line.to_string(), Value::from("")),
+                        })
+                        .collect(),
+                ),
+            };
+            obj.insert(key.to_string(), value);
+        }
+        Value::Object(obj)
+    }
+
+    fn uid(&self) -> String {
+        self.values
+            .get("handler.uid")
+            .map(|uid| uid.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    fn dir(&self) -> PathBuf {
+        match &self.path_handler {
+            Some(dir) => dir.clone(),
+            None => PATH_PARTY.join("handlers").join(self.uid()),
+        }
+    }
+
+    // Writes the draft to handler.json, keeping keys the editor doesn't show. Returns the handler's
+    // folder and, for a converted executable, the data that couldn't be moved to the new uid.
+    fn save(&self) -> Result<(PathBuf, Vec<PathBuf>), Box<dyn Error>> {
+        let dir = self.dir();
+        if self.path_handler.is_none() && dir.exists() {
+            return Err(
+                format!("A handler with uid \"{}\" is already installed", self.uid()).into(),
+            );
+        }
+
+        let json = self.to_json();
+        Handler::from_json(&json, &dir)?.validate()?;
+
+        let json_path = dir.join("handler.json");
+        let mut out = match std::fs::read_to_string(&json_path) {
+            Ok(contents) => match serde_json::from_str(&contents)? {
+                Value::Object(obj) => obj,
+                _ => return Err("handler.json is not an object".into()),
+            },
+            Err(_) => Map::new(),
+        };
+        // Unset keys are left out. A handler that extends another keeps the keys its file sets and
+        // adds the ones that differ from the base, so later changes to the base still reach it.
+        for (key, value) in json.as_object().into_iter().flatten() {
+            let keep = match &self.base {
+                Some(base) => out.contains_key(key) || base[key] != *value,
+                None => match value {
+                    Value::Bool(b) => *b,
+                    Value::Number(n) => n.as_u64() != Some(0),
+                    Value::String(s) => !s.is_empty(),
+                    Value::Array(arr) => !arr.is_empty(),
+                    Value::Object(obj) => !obj.is_empty(),
+                    _ => false,
+                },
+            };
+            if keep {
+                out.insert(key.clone(), value.clone());
+            } else {
+                out.remove(key);
+            }
+        }
+
+        std::fs::create_dir_all(&dir)?;
+        // A crash mid-write mustn't leave a truncated handler.json behind. No .bak is kept, as it
+        // would end up in exported handlers.
+        write_atomic(
+            &json_path,
+            serde_json::to_string_pretty(&out)?.as_bytes(),
+            false,
+        )?;
+
+        let mut left_behind = Vec::new();
+        if let Some(entry) = &self.convert_from {
+            if entry.path_icon().exists() && !dir.join("icon.png").exists() {
+                std::fs::copy(entry.path_icon(), dir.join("icon.png"))?;
+            }
+            add_path(&self.uid(), &entry.root().to_string_lossy().to_string())?;
+            // Saves, settings and play history follow the game to its new uid
+            left_behind = move_game_data(&entry.uid, &self.uid())?;
+            remove_game(&Game::Executable(entry.clone()))?;
+        }
+
+        Ok((dir, left_behind))
+    }
+}
+
+impl PartyApp {
+    // Opens the editor for a handler, or for a new handler that replaces an executable
+    pub fn open_handler_editor(&mut self, game: usize) {
+        self.editor = match &self.games[game] {
+            Game::HandlerRef(h) => {
+                // Loaded from disk, as the games list may hold one of the handler's variants
+                let editor = match HandlerEditor::open(&h.path_handler) {
+                    Ok(editor) => editor,
+                    Err(err) => {
+                        msg("Error", &format!("Couldn't load handler: {err}"));
+                        return;
+                    }
+                };
+                HandlerEditor {
+                    path_root: peek_rootpath_handler(h).map(PathBuf::from),
+                    ..editor
+                }
+            }
+            Game::Executable(entry) => {
+                let mut h = entry.to_handler();
+                h.uid = h
+                    .name
+                    .chars()
+                    .filter(|c| c.is_ascii_alphanumeric())
+                    .collect();
+                h.info = String::new();
+                HandlerEditor {
+                    convert_from: Some(entry.clone()),
+                    path_root: Some(entry.root()),
+                    ..HandlerEditor::load(&h.to_json())
+                }
+            }
+        };
+        self.cur_page = MenuPage::Editor;
+    }
+
+    pub fn display_page_editor(&mut self, ui: &mut Ui) {
+        let mut json = self.editor.to_json();
+        if let Value::Object(obj) = &mut json {
+            obj.extend(self.editor.extra.clone());
+        }
+        if json != self.editor.linted {
+            self.editor.diagnostics = lint_handler_json(&json, &self.editor.dir());
+            self.editor.linted = json;
+        }
+
+        ui.horizontal(|ui| {
+            match &self.editor.convert_from {
+                Some(entry) => ui.heading(format!("New handler for {}", entry.display())),
+                None => ui.heading(format!("Edit handler: {}", self.editor.uid())),
+            };
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Cancel").clicked() {
+                    self.cur_page = MenuPage::Game;
+                }
+                if ui.button("Save").clicked() {
+                    self.save_handler_editor();
+                }
+            });
+        });
+        ui.separator();
+
+        let path_root = match &self.editor.path_root {
+            Some(path) => path.display().to_string(),
+            None => "not found".to_string(),
+        };
+        ui.label(format!("Game folder: {path_root}"));
+
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 100.0)
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                egui::Grid::new("handler_editor")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let mut section = "";
+                        for (key, kind, info) in FIELDS {
+                            if section_title(key) != section {
+                                section = section_title(key);
+                                ui.label(RichText::new(section).strong());
+                                ui.end_row();
+                            }
+                            let label = ui.label(*key);
+                            let field = self.display_editor_field(ui, key, *kind);
+                            if label.hovered() || field.hovered() {
+                                self.infotext = format!("{key}: {info}");
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("handler_editor_problems")
+            .show(ui, |ui| {
+                if self.editor.diagnostics.is_empty() {
+                    ui.label("✔ No problems found.");
+                }
+                for d in &self.editor.diagnostics {
+                    let color = match d.severity {
+                        Severity::Error => egui::Color32::LIGHT_RED,
+                        Severity::Warning => egui::Color32::YELLOW,
+                    };
+                    ui.label(RichText::new(d.to_string()).color(color));
+                }
+            });
+    }
+
+    fn display_editor_field(
+        &mut self,
+        ui: &mut Ui,
+        key: &'static str,
+        kind: FieldKind,
+    ) -> egui::Response {
+        if kind == Flag {
+            return ui.checkbox(self.editor.flags.entry(key).or_default(), "");
+        }
+
+        // A saved handler's uid names its folder and its saves, so it stays fixed
+        let editable = key != "handler.uid" || self.editor.path_handler.is_none();
+        let mut picked = None;
+        let response = ui
+            .horizontal(|ui| {
+                let value = self.editor.values.entry(key).or_default();
+                match kind {
+                    Text | Number | GamePath => {
+                        ui.add_enabled(editable, egui::TextEdit::singleline(value));
+                    }
+                    _ => {
+                        ui.add(egui::TextEdit::multiline(value).desired_rows(2));
+                    }
+                }
+                match kind {
+                    GamePath if ui.button("Browse...").clicked() => {
+                        picked = Some(key == "steam.api_path");
+                    }
+                    GamePathList => {
+                        ui.vertical(|ui| {
+                            if ui.button("Add File...").clicked() {
+                                picked = Some(false);
+                            }
+                            if ui.button("Add Folder...").clicked() {
+                                picked = Some(true);
+                            }
+                        });
+                    }
+                    _ => {}
+                }
+            })
+            .response;
+
+        if let Some(folder) = picked
+            && let Some(path) = self.pick_game_path(folder)
+        {
+            let value = self.editor.values.entry(key).or_default();
+            match kind {
+                GamePathList => {
+                    if !value.is_empty() && !value.ends_with('\n') {
+                        value.push('\n');
+                    }
+                    value.push_str(&path);
+                }
+                _ => *value = path,
+            }
+        }
+
+        response
+    }
+
+    // Asks for a file or folder inside the game folder and returns its path relative to it
+    fn pick_game_path(&mut self, folder: bool) -> Option<String> {
+        if self.editor.path_root.is_none() {
+            let path = rfd::FileDialog::new()
+                .set_title("Locate the game folder")
+                .set_directory(&*PATH_HOME)
+                .pick_folder()?;
+            let uid = self.editor.uid();
+            if !uid.is_empty()
+                && let Err(err) = add_path(&uid, &path.to_string_lossy().to_string())
+            {
+                msg("Error", &format!("Couldn't save location: {err}"));
+            }
+            self.editor.path_root = Some(path);
+        }
+        let root = self.editor.path_root.clone()?;
+
+        let dialog = rfd::FileDialog::new().set_directory(&root);
+        let picked = match folder {
+            true => dialog.set_title("Select folder").pick_folder()?,
+            false => dialog.set_title("Select file").pick_file()?,
+        };
+        match picked.strip_prefix(&root) {
+            Ok(rel) if rel.as_os_str().is_empty() => Some(".".to_string()),
+            Ok(rel) => Some(rel.to_string_lossy().to_string()),
+            Err(_) => {
+                msg(
+                    "Error",
+                    &format!(
+                        "{} is not inside the game folder {}",
+                        picked.display(),
+                        root.display()
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    fn save_handler_editor(&mut self) {
+        let errors: Vec<String> = self
+            .editor
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.to_string())
+            .collect();
+        if !errors.is_empty()
+            && !yesno(
+                "Save handler?",
+                &format!(
+                    "The handler has problems:\n{}\n\nSave anyway?",
+                    errors.join("\n")
+                ),
+            )
+        {
+            return;
+        }
+
+        match self.editor.save() {
+            Ok((dir, left_behind)) => {
+                if !left_behind.is_empty() {
+                    let paths: Vec<String> = left_behind
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect();
+                    msg(
+                        "Data Left Behind",
+                        &format!(
+                            "The handler already had data in these places, so the executable's data was left where it is:\n{}",
+                            paths.join("\n")
+                        ),
+                    );
+                }
+                self.editor.path_handler = Some(dir);
+                self.editor.convert_from = None;
+                self.games = scan_all_games();
+                let uid = self.editor.uid();
+                if let Some(i) = self.games.iter().position(|game| game.uid() == uid) {
+                    self.selected_game = i;
+                }
+                self.cur_page = MenuPage::Game;
+            }
+            Err(err) => msg("Error", &format!("Couldn't save handler: {err}")),
+        }
+    }
+}
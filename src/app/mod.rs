@@ -2,7 +2,10 @@ mod app;
 mod config;
 mod gui_pages;
 mod gui_panels;
+mod handler_editor;
 
 pub use app::PartyApp;
 pub use config::PadFilterType;
 pub use config::PartyConfig;
+pub use config::load_cfg;
+pub use config::{GameOverrides, load_overrides};
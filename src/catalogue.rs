@@ -0,0 +1,133 @@
+use crate::handler::install_handler_from_file;
+use crate::paths::*;
+
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::PathBuf;
+
+// An installable handler listed in a handler index
+#[derive(Deserialize, Clone)]
+pub struct CatalogueEntry {
+    pub uid: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(alias = "download_url")]
+    pub url: String,
+    pub sha256: String,
+}
+
+impl CatalogueEntry {
+    pub fn display(&self) -> &str {
+        if self.name.is_empty() {
+            self.uid.as_str()
+        } else {
+            self.name.as_str()
+        }
+    }
+}
+
+// Index URLs can be http(s) URLs, file:// URLs or plain local paths, so a local file or mirror can stand in
+fn read_url(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let response = reqwest::blocking::Client::new()
+            .get(url)
+            .header("User-Agent", "partydeck")
+            .send()?
+            .error_for_status()?;
+        return Ok(response.bytes()?.to_vec());
+    }
+
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    Ok(std::fs::read(path)?)
+}
+
+// Download URLs without a scheme are relative to the index location
+fn resolve_url(index_url: &str, url: &str) -> String {
+    if url.contains("://") || url.starts_with('/') {
+        return url.to_string();
+    }
+    match index_url.rsplit_once('/') {
+        Some((base, _)) => format!("{base}/{url}"),
+        None => url.to_string(),
+    }
+}
+
+pub fn fetch_catalogue(index_url: &str) -> Result<Vec<CatalogueEntry>, Box<dyn Error>> {
+    if index_url.trim().is_empty() {
+        return Err("No handler index URL set".into());
+    }
+
+    let json: Value = serde_json::from_slice(&read_url(index_url.trim())?)?;
+
+    // Accept either a bare list of handlers or {"handlers": [...]}
+    let list = match json.get("handlers") {
+        Some(handlers) => handlers.clone(),
+        None => json,
+    };
+
+    let mut entries: Vec<CatalogueEntry> = serde_json::from_value(list)?;
+    entries.retain(|e| !e.uid.is_empty() && e.uid.chars().all(char::is_alphanumeric));
+    for entry in &mut entries {
+        entry.url = resolve_url(index_url.trim(), &entry.url);
+    }
+    entries.sort_by_key(|e| e.display().to_lowercase());
+
+    Ok(entries)
+}
+
+pub fn install_from_catalogue(entry: &CatalogueEntry) -> Result<(), Box<dyn Error>> {
+    println!("Downloading handler {} from {}", entry.uid, entry.url);
+    let data = read_url(&entry.url)?;
+
+    let checksum = format!("{:x}", Sha256::digest(&data));
+    if !checksum.eq_ignore_ascii_case(entry.sha256.trim()) {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {checksum}",
+            entry.uid, entry.sha256
+        )
+        .into());
+    }
+
+    let dir_downloads = PATH_PARTY.join("downloads");
+    std::fs::create_dir_all(&dir_downloads)?;
+    let path_pdh: PathBuf = dir_downloads.join(format!("{}.pdh", entry.uid));
+    std::fs::write(&path_pdh, data)?;
+
+    let result = install_handler_from_file(&path_pdh, Some(&entry.uid));
+    let _ = std::fs::remove_file(&path_pdh);
+    result
+}
+
+// True if the version in the index is newer than the installed one
+pub fn is_newer_version(index_version: &str, installed_version: &str) -> bool {
+    let parse = |v: &str| semver::Version::parse(v.trim().trim_start_matches('v'));
+    match (parse(index_version), parse(installed_version)) {
+        (Ok(index), Ok(installed)) => index > installed,
+        _ => match (
+            numeric_version(index_version),
+            numeric_version(installed_version),
+        ) {
+            // Handler versions are often just "1.2", so compare dot-separated numbers
+            (Some(index), Some(installed)) => index > installed,
+            // Anything else can only be compared for equality
+            _ => !index_version.is_empty() && index_version.trim() != installed_version.trim(),
+        },
+    }
+}
+
+fn numeric_version(version: &str) -> Option<Vec<u64>> {
+    let mut parts = version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    while parts.len() > 1 && parts.last() == Some(&0) {
+        parts.pop();
+    }
+    Some(parts)
+}
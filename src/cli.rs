@@ -0,0 +1,167 @@
+use crate::app::load_cfg;
+use crate::desktop_entries::*;
+use crate::handler::*;
+use crate::lint::*;
+use crate::paths::*;
+use crate::presets::*;
+use crate::signing::*;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: partydeck [--fullscreen]
+       partydeck export-handler <handler dir|uid> [output dir]
+       partydeck lint-handler <handler dir|.pdh|uid>
+       partydeck launch <game uid> [--preset <name>]
+       partydeck launch [<game uid>] --preset <name>
+       partydeck url partydeck://launch/<game uid>[?preset=<name>]
+       partydeck keygen
+       partydeck trust-key <name> <public key>";
+
+// Runs a command-line subcommand if one was given.
+// Returns None when PartyDeck should start the GUI, or the process exit code otherwise.
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    let command = args.get(1)?;
+
+    // Menu entries and links without a preset open the game's page in the GUI
+    if start_game(args).is_some() {
+        return None;
+    }
+
+    let result = match command.as_str() {
+        "export-handler" => cmd_export_handler(&args[2..]),
+        "lint-handler" => cmd_lint_handler(&args[2..]),
+        "launch" => parse_launch_args(&args[2..]).and_then(cmd_launch),
+        "url" => match args.get(2) {
+            Some(url) => parse_launch_url(url).and_then(cmd_launch),
+            None => Err(USAGE.into()),
+        },
+        "keygen" => cmd_keygen(),
+        "trust-key" => cmd_trust_key(&args[2..]),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        // Flags such as --fullscreen are handled by the GUI
+        arg if arg.starts_with("--") => return None,
+        _ => Err(format!("Unknown command \"{command}\"\n{USAGE}").into()),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(err) => {
+            eprintln!("Error: {err}");
+            Some(1)
+        }
+    }
+}
+
+// Accepts either a path to a handler folder or the uid of an installed handler
+fn find_handler(arg: &str) -> Result<Handler, Box<dyn Error>> {
+    let mut path = PathBuf::from(arg);
+    if !path.join("handler.json").exists() {
+        path = PATH_PARTY.join("handlers").join(arg);
+    }
+    if !path.join("handler.json").exists() {
+        return Err(format!("No handler found at \"{arg}\"").into());
+    }
+    Handler::new(&path.join("handler.json"))
+}
+
+fn cmd_export_handler(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let handler = find_handler(args.first().ok_or(USAGE)?)?;
+    let dest_dir = match args.get(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_dir()?,
+    };
+
+    let path_pdh = export_handler(&handler, &dest_dir)?;
+    println!("{}", path_pdh.display());
+    Ok(())
+}
+
+fn cmd_lint_handler(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let arg = args.first().ok_or(USAGE)?;
+    let mut path = PathBuf::from(arg);
+    if !path.exists() {
+        path = PATH_PARTY.join("handlers").join(arg);
+    }
+
+    let diagnostics = lint_handler_path(&path)?;
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    println!(
+        "{}: {errors} error(s), {} warning(s)",
+        path.display(),
+        diagnostics.len() - errors
+    );
+
+    match errors {
+        0 => Ok(()),
+        _ => Err("Handler has errors".into()),
+    }
+}
+
+// The game to show when PartyDeck was started from a menu entry or link without a preset
+pub fn start_game(args: &[String]) -> Option<String> {
+    let target = match args.get(1)?.as_str() {
+        "launch" => parse_launch_args(&args[2..]),
+        "url" => parse_launch_url(args.get(2)?),
+        _ => return None,
+    };
+    match target {
+        Ok(LaunchTarget {
+            game: Some(game),
+            preset: None,
+        }) => Some(game),
+        _ => None,
+    }
+}
+
+// Accepts [game uid] [--preset <name>], in any order
+fn parse_launch_args(args: &[String]) -> Result<LaunchTarget, Box<dyn Error>> {
+    let mut target = LaunchTarget {
+        game: None,
+        preset: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--preset" => target.preset = Some(args.next().ok_or(USAGE)?.clone()),
+            _ if target.game.is_none() && !arg.starts_with("--") => target.game = Some(arg.clone()),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    Ok(target)
+}
+
+fn cmd_launch(target: LaunchTarget) -> Result<(), Box<dyn Error>> {
+    let Some(name) = target.preset else {
+        return Err(USAGE.into());
+    };
+    let preset = find_preset(target.game.as_deref(), &name)?;
+    launch_preset(&preset, &load_cfg())
+}
+
+fn cmd_keygen() -> Result<(), Box<dyn Error>> {
+    let key = generate_signing_key()?;
+    println!("Created a signing key. Exported handlers will now be signed.");
+    println!("Share this public key with people who install your handlers:\n{key}");
+    Ok(())
+}
+
+fn cmd_trust_key(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (name, key) = match args {
+        [name, key] => (name, key),
+        _ => return Err(USAGE.into()),
+    };
+    trust_key(name, key)?;
+    println!("Handlers signed with this key will be shown as published by {name}");
+    Ok(())
+}
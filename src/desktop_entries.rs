@@ -0,0 +1,215 @@
+use crate::game::Game;
+use crate::paths::*;
+use crate::presets::Preset;
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+// Where a partydeck:// link tells PartyDeck to go
+#[derive(Clone, PartialEq, Debug)]
+pub struct LaunchTarget {
+    // Uid of the game
+    pub game: Option<String>,
+    pub preset: Option<String>,
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::new();
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Result<String, Box<dyn Error>> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3).ok_or("Incomplete escape in URL")?;
+            out.push(u8::from_str_radix(hex, 16)?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(String::from_utf8(out)?)
+}
+
+// e.g. partydeck://launch/MyGame?preset=Couch%20Night
+pub fn launch_url(game: &str, preset: Option<&str>) -> String {
+    match preset {
+        Some(preset) => format!(
+            "partydeck://launch/{}?preset={}",
+            percent_encode(game),
+            percent_encode(preset)
+        ),
+        None => format!("partydeck://launch/{}", percent_encode(game)),
+    }
+}
+
+pub fn parse_launch_url(url: &str) -> Result<LaunchTarget, Box<dyn Error>> {
+    let rest = url
+        .strip_prefix("partydeck://launch/")
+        .ok_or_else(|| format!("Not a partydeck://launch/ URL: \"{url}\""))?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let game = percent_decode(path.trim_end_matches('/'))?;
+    let mut preset = None;
+    for pair in query.split('&') {
+        if let Some(value) = pair.strip_prefix("preset=") {
+            preset = Some(percent_decode(value)?);
+        }
+    }
+
+    Ok(LaunchTarget {
+        game: Some(game).filter(|game| !game.is_empty()),
+        preset: preset.filter(|preset| !preset.is_empty()),
+    })
+}
+
+// Quotes an argument for the Exec key of a desktop entry
+fn exec_arg(arg: &str) -> String {
+    let mut out = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    // Exec is a string value, whose backslash escapes are undone before the quoting is read.
+    // Percent signs are field codes, even inside quotes.
+    out.replace('\\', "\\\\").replace('%', "%%")
+}
+
+fn path_applications() -> PathBuf {
+    PATH_LOCAL_SHARE.join("applications")
+}
+
+// Desktop entry file names can't contain spaces or slashes
+fn desktop_file_name(id: &str) -> String {
+    let id: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("partydeck-{id}.desktop")
+}
+
+fn game_icon_path(game: &Game) -> PathBuf {
+    let icon = match game {
+        Game::Executable(entry) => entry.path_icon(),
+        Game::HandlerRef(h) => h.asset("icon.png"),
+    };
+    match icon.exists() {
+        true => icon,
+        false => PATH_RES.join("icon.png"),
+    }
+}
+
+fn write_desktop_entry(
+    file_name: &str,
+    name: &str,
+    comment: &str,
+    icon: &Path,
+    args: &[&str],
+    extra: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let exe = std::env::current_exe()?;
+    let mut exec = exec_arg(&exe.to_string_lossy());
+    for arg in args {
+        exec.push(' ');
+        exec.push_str(arg);
+    }
+
+    let contents = format!(
+        "[Desktop Entry]\nType=Application\nName={name}\nComment={comment}\nIcon={}\nExec={exec}\nTerminal=false\nCategories=Game;\n{extra}",
+        icon.display()
+    );
+
+    std::fs::create_dir_all(path_applications())?;
+    let path = path_applications().join(file_name);
+    std::fs::write(&path, contents)?;
+    Ok(path)
+}
+
+// Adds the game to the applications menu. It opens PartyDeck on the game's page.
+pub fn create_game_desktop_entry(game: &Game) -> Result<PathBuf, Box<dyn Error>> {
+    let path = write_desktop_entry(
+        &desktop_file_name(game.uid()),
+        &game.name(),
+        "Play with PartyDeck",
+        &game_icon_path(game),
+        &["launch", &exec_arg(game.uid())],
+        "",
+    )?;
+    register_url_handler()?;
+    Ok(path)
+}
+
+// Adds the preset to the applications menu. It starts the preset's players right away.
+pub fn create_preset_desktop_entry(
+    preset: &Preset,
+    game: &Game,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let path = write_desktop_entry(
+        &desktop_file_name(&format!("{}-{}", game.uid(), preset.name)),
+        &format!("{} ({})", game.name(), preset.name),
+        &format!("Play with PartyDeck using the {} preset", preset.name),
+        &game_icon_path(game),
+        &[
+            "launch",
+            &exec_arg(game.uid()),
+            "--preset",
+            &exec_arg(&preset.name),
+        ],
+        "",
+    )?;
+    register_url_handler()?;
+    Ok(path)
+}
+
+// Makes partydeck:// links open in PartyDeck
+pub fn register_url_handler() -> Result<(), Box<dyn Error>> {
+    let file_name = "partydeck-url.desktop";
+    write_desktop_entry(
+        file_name,
+        "PartyDeck",
+        "Opens partydeck:// links",
+        &PATH_RES.join("icon.png"),
+        &["url", "%u"],
+        "NoDisplay=true\nMimeType=x-scheme-handler/partydeck;\n",
+    )?;
+
+    // xdg-mime isn't installed everywhere, and the entry's MimeType= is often enough on its own
+    match std::process::Command::new("xdg-mime")
+        .args(["default", file_name, "x-scheme-handler/partydeck"])
+        .status()
+    {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            println!("Warning: xdg-mime couldn't register the partydeck:// URL handler ({status})")
+        }
+        Err(err) => {
+            println!("Warning: couldn't run xdg-mime to register partydeck:// links: {err}")
+        }
+    }
+    // Not every system has it, and menus pick up new entries without it anyway
+    let _ = std::process::Command::new("update-desktop-database")
+        .arg(path_applications())
+        .status();
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use crate::handler::Handler;
+use crate::paths::*;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+// A program added with Add Game, with the settings a handler would otherwise provide
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ExecutableEntry {
+    pub path: String,
+    pub uid: String,
+    pub name: String,
+    pub args: Vec<String>,
+    // Folder the game runs from; the executable's folder if empty
+    pub workdir: String,
+    pub runtime: String,
+    pub unique_appdata: bool,
+    pub unique_documents: bool,
+    pub unique_localshare: bool,
+    pub unique_config: bool,
+    pub env: BTreeMap<String, String>,
+}
+
+// paths.json used to list executables as plain paths
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExecutableRecord {
+    Path(String),
+    Entry(ExecutableEntry),
+}
+
+pub fn deserialize_executables<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<ExecutableEntry>, D::Error> {
+    let records = Vec::<ExecutableRecord>::deserialize(deserializer)?;
+    Ok(records
+        .into_iter()
+        .map(|record| match record {
+            ExecutableRecord::Path(path) => ExecutableEntry::new(&path),
+            ExecutableRecord::Entry(entry) if entry.uid.is_empty() => ExecutableEntry {
+                uid: executable_uid(&entry.path),
+                ..entry
+            },
+            ExecutableRecord::Entry(entry) => entry,
+        })
+        .collect())
+}
+
+// Executables get a stable uid from their path (FNV-1a), so overrides, saves and prefixes can be keyed by it
+pub fn executable_uid(path: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in path.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("exe{hash:016x}")
+}
+
+impl ExecutableEntry {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            uid: executable_uid(path),
+            ..Default::default()
+        }
+    }
+
+    pub fn filename(&self) -> String {
+        PathBuf::from(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn display(&self) -> String {
+        match self.name.is_empty() {
+            true => self.filename(),
+            false => self.name.clone(),
+        }
+    }
+
+    pub fn is_win(&self) -> bool {
+        PathBuf::from(&self.path).extension().unwrap_or_default() == "exe"
+    }
+
+    // Holds the executable's icon and anything else PartyDeck keeps for it
+    pub fn path_data(&self) -> PathBuf {
+        PATH_PARTY.join("executables").join(&self.uid)
+    }
+
+    pub fn path_icon(&self) -> PathBuf {
+        self.path_data().join("icon.png")
+    }
+
+    // The folder the game runs from
+    pub fn root(&self) -> PathBuf {
+        let path = PathBuf::from(&self.path);
+        if !self.workdir.is_empty() && path.starts_with(&self.workdir) {
+            return PathBuf::from(&self.workdir);
+        }
+        path.parent().map(|p| p.to_path_buf()).unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.workdir.is_empty() && !PathBuf::from(&self.path).starts_with(&self.workdir) {
+            return Err("The working directory must contain the executable".into());
+        }
+        if !["", "scout", "soldier"].contains(&self.runtime.as_str()) {
+            return Err(format!("Unknown runtime \"{}\"", self.runtime).into());
+        }
+        if let Some(key) = self
+            .env
+            .keys()
+            .find(|k| k.is_empty() || !k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        {
+            return Err(format!("Invalid environment variable name: {key}").into());
+        }
+        Ok(())
+    }
+
+    // Launching and save handling work on handlers, so executables are run as a generated one
+    pub fn to_handler(&self) -> Handler {
+        let root = self.root();
+        let exec = PathBuf::from(&self.path)
+            .strip_prefix(&root)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| self.filename());
+
+        Handler {
+            path_handler: self.path_data(),
+            root: Some(root),
+            uid: self.uid.clone(),
+            name: self.display(),
+            info: self.path.clone(),
+            win: self.is_win(),
+            runtime: self.runtime.clone(),
+            exec,
+            args: self.args.clone(),
+            env: self.env.clone().into_iter().collect(),
+            win_unique_appdata: self.unique_appdata,
+            win_unique_documents: self.unique_documents,
+            linux_unique_localshare: self.unique_localshare,
+            linux_unique_config: self.unique_config,
+            ..Default::default()
+        }
+    }
+}
+
+// Saves an image as the executable's icon. Windows executables can be given directly,
+// their icon is then extracted with wrestool from icoutils.
+pub fn set_executable_icon(entry: &ExecutableEntry, src: &PathBuf) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(entry.path_data())?;
+
+    if src.extension().unwrap_or_default() != "exe" {
+        image::open(src)?.save(entry.path_icon())?;
+        return Ok(());
+    }
+
+    let dir_tmp = PATH_PARTY
+        .join("tmp")
+        .join(format!("icon-{}", fastrand::u32(..)));
+    std::fs::create_dir_all(&dir_tmp)?;
+
+    let result = extract_exe_icon(src, &dir_tmp, &entry.path_icon());
+    let _ = std::fs::remove_dir_all(&dir_tmp);
+    result
+}
+
+fn extract_exe_icon(
+    exe: &PathBuf,
+    dir_tmp: &PathBuf,
+    dest: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let status = std::process::Command::new("wrestool")
+        .args(["-x", "-t", "14", "-o"])
+        .arg(dir_tmp)
+        .arg(exe)
+        .status()
+        .map_err(|_| "wrestool not found, install icoutils to extract icons")?;
+    if !status.success() {
+        return Err("Couldn't extract an icon from the executable".into());
+    }
+
+    // Executables can hold several icon groups; the biggest file usually has the largest sizes
+    let ico = std::fs::read_dir(dir_tmp)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().unwrap_or_default() == "ico")
+        .max_by_key(|path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+        .ok_or("The executable has no icon")?;
+
+    image::open(ico)?.save(dest)?;
+    Ok(())
+}
@@ -1,16 +1,18 @@
+use crate::executable::{ExecutableEntry, set_executable_icon};
 use crate::handler::{Handler, install_handler_from_file, scan_handlers};
+use crate::history::rename_history_game;
+use crate::library::rename_library_game;
 use crate::paths::*;
+use crate::util::PathsStore;
 
 use eframe::egui::{self, ImageSource};
 use rfd::FileDialog;
-use serde_json::Value;
+use std::borrow::Cow;
 use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
 use std::path::PathBuf;
 
 pub enum Game {
-    Executable { path: PathBuf, filename: String },
+    Executable(ExecutableEntry),
     HandlerRef(Handler),
 }
 
@@ -19,26 +21,39 @@ impl ToOwned for Game {
 
     fn to_owned(&self) -> Self::Owned {
         match self {
-            Game::Executable { path, filename } => Game::Executable {
-                path: path.clone(),
-                filename: filename.clone(),
-            },
+            Game::Executable(entry) => Game::Executable(entry.clone()),
             Game::HandlerRef(handler) => Game::HandlerRef(handler.clone()),
         }
     }
 }
 impl Game {
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> String {
         match self {
-            Game::Executable { filename, .. } => filename,
-            Game::HandlerRef(handler) => handler.display(),
+            Game::Executable(entry) => entry.display(),
+            Game::HandlerRef(handler) => handler.display().to_string(),
+        }
+    }
+    pub fn uid(&self) -> &str {
+        match self {
+            Game::Executable(entry) => &entry.uid,
+            Game::HandlerRef(handler) => &handler.uid,
+        }
+    }
+    // Executables are launched and have their saves managed through a generated handler
+    pub fn handler(&self) -> Cow<'_, Handler> {
+        match self {
+            Game::Executable(entry) => Cow::Owned(entry.to_handler()),
+            Game::HandlerRef(handler) => Cow::Borrowed(handler),
         }
     }
     pub fn icon(&self) -> ImageSource {
         match self {
-            Game::Executable { .. } => egui::include_image!("../res/executable_icon.png"),
+            Game::Executable(entry) if entry.path_icon().exists() => {
+                format!("file://{}", entry.path_icon().display()).into()
+            }
+            Game::Executable(_) => egui::include_image!("../res/executable_icon.png"),
             Game::HandlerRef(handler) => {
-                format!("file://{}/icon.png", handler.path_handler.display()).into()
+                format!("file://{}", handler.asset("icon.png").display()).into()
             }
         }
     }
@@ -53,22 +68,8 @@ pub fn scan_all_games() -> Vec<Game> {
     }
 
     // Scan executable paths from paths.json
-    if let Ok(file) = std::fs::File::open(PATH_PARTY.join("paths.json")) {
-        let json: Value = serde_json::from_reader(BufReader::new(file)).unwrap_or_default();
-
-        if let Some(executables) = json[".executables"].as_array() {
-            for executable in executables {
-                if let Some(path_str) = executable.as_str() {
-                    let path = PathBuf::from(path_str);
-                    let filename = path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("")
-                        .to_string();
-                    games.push(Game::Executable { path, filename });
-                }
-            }
-        }
+    for entry in PathsStore::load().executables {
+        games.push(Game::Executable(entry));
     }
 
     // Sort the games by name
@@ -99,67 +100,93 @@ pub fn add_game() -> Result<(), Box<dyn Error>> {
     };
 
     if file.extension().unwrap_or_default() == "pdh" {
-        install_handler_from_file(&file)?;
+        install_handler_from_file(&file, None)?;
     }
 
     // Add executable path to the paths.json file
     if file.extension().unwrap_or_default() != "pdh" {
-        // Prepare the JSON data - either load existing or create new
-        let mut json = if let Ok(file) = File::open(PATH_PARTY.join("paths.json")) {
-            serde_json::from_reader(BufReader::new(file))
-                .unwrap_or(Value::Object(serde_json::Map::new()))
-        } else {
-            Value::Object(serde_json::Map::new())
-        };
-
-        // Ensure the executables array exists
-        if !json.as_object().unwrap().contains_key(".executables") {
-            json[".executables"] = serde_json::Value::Array(Vec::new());
+        let entry = ExecutableEntry::new(&file.to_string_lossy());
+        if add_executable(&entry)?
+            && entry.is_win()
+            && let Err(err) = set_executable_icon(&entry, &file)
+        {
+            println!("Couldn't extract icon from {}: {err}", file.display());
         }
+    }
 
-        // Add the file path to the executables array
-        if let Some(executables) = json[".executables"].as_array_mut() {
-            let file_path = file.to_string_lossy().to_string();
+    Ok(())
+}
 
-            // Only add if not already present
-            if !executables.iter().any(|p| p.as_str() == Some(&file_path)) {
-                let path_value = serde_json::Value::String(file_path);
-                executables.push(path_value);
-            }
+// Adds the executable to paths.json, returning false if it was already there
+pub fn add_executable(entry: &ExecutableEntry) -> Result<bool, Box<dyn Error>> {
+    PathsStore::update(|store| {
+        // Only add if not already present
+        let added = !store.executables.iter().any(|e| e.uid == entry.uid);
+        if added {
+            store.executables.push(entry.clone());
         }
+        added
+    })
+    .map_err(|e| format!("Failed to write paths.json: {}", e).into())
+}
 
-        // Save the updated paths.json
-        let updated_data = serde_json::to_string_pretty(&json)
-            .map_err(|e| format!("Failed to serialize paths.json: {}", e))?;
+// Moves what PartyDeck keeps under a game's uid to new_uid: profile saves, game settings, the Wine
+// prefix, library stats and play history. Used when a handler replaces an executable.
+// Anything new_uid already has is left alone; returns the paths that couldn't be moved for that reason.
+pub fn move_game_data(old_uid: &str, new_uid: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut moves = vec![
+        (
+            PATH_PARTY
+                .join("game_overrides")
+                .join(format!("{old_uid}.json")),
+            PATH_PARTY
+                .join("game_overrides")
+                .join(format!("{new_uid}.json")),
+        ),
+        (
+            PATH_PARTY.join("pfx").join(old_uid),
+            PATH_PARTY.join("pfx").join(new_uid),
+        ),
+    ];
+    if let Ok(entries) = std::fs::read_dir(PATH_PARTY.join("profiles")) {
+        for entry in entries.flatten() {
+            let saves = entry.path().join("saves");
+            moves.push((saves.join(old_uid), saves.join(new_uid)));
+        }
+    }
 
-        std::fs::write(PATH_PARTY.join("paths.json"), updated_data)
-            .map_err(|e| format!("Failed to write paths.json: {}", e))?;
+    let mut left_behind = Vec::new();
+    for (src, dest) in moves {
+        if !src.exists() {
+            continue;
+        }
+        if dest.exists() {
+            println!(
+                "Not moving {} to {}, which already exists",
+                src.display(),
+                dest.display()
+            );
+            left_behind.push(src);
+            continue;
+        }
+        std::fs::rename(&src, &dest)?;
     }
 
-    Ok(())
+    rename_library_game(old_uid, new_uid)?;
+    rename_history_game(old_uid, new_uid)?;
+    Ok(left_behind)
 }
 
 pub fn remove_game(game: &Game) -> Result<(), Box<dyn Error>> {
     match game {
-        Game::Executable { path, .. } => {
-            // Load current paths.json
-            let mut json = if let Ok(file) = File::open(PATH_PARTY.join("paths.json")) {
-                serde_json::from_reader(BufReader::new(file))
-                    .unwrap_or(Value::Object(serde_json::Map::new()))
-            } else {
-                Value::Object(serde_json::Map::new())
-            };
-
-            // Remove the file path from the executables array
-            if let Some(executables) = json[".executables"].as_array_mut() {
-                let file_path = path.to_string_lossy().to_string();
-                executables.retain(|p| p.as_str() != Some(&file_path));
+        Game::Executable(entry) => {
+            PathsStore::update(|store| {
+                store.executables.retain(|e| e.uid != entry.uid);
+                store.roots.remove(&entry.uid);
+            })?;
+            if entry.path_data().exists() {
+                std::fs::remove_dir_all(entry.path_data())?;
             }
-
-            // Save the updated paths.json
-            let updated_data = serde_json::to_string_pretty(&json)?;
-
-            std::fs::write(PATH_PARTY.join("paths.json"), updated_data)?;
         }
 
         Game::HandlerRef(h) => {
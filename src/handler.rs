@@ -1,16 +1,23 @@
+use crate::app::PartyConfig;
 use crate::paths::*;
+use crate::signing::*;
 use crate::util::*;
 
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::error::Error;
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{BufReader, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Handler {
     // Members that are determined by context
     pub path_handler: PathBuf,
+    // Install folder of handlers generated from executables; other handlers look theirs up in paths.json
+    pub root: Option<PathBuf>,
+    // Folders of the handlers this one extends, nearest first, see asset
+    pub base_dirs: Vec<PathBuf>,
     pub img_paths: Vec<PathBuf>,
 
     pub uid: String,
@@ -23,31 +30,78 @@ pub struct Handler {
     pub win: bool,
     pub runtime: String,
     pub is32bit: bool,
+    // 0 if the handler doesn't say
+    pub max_players: u32,
     pub exec: String,
     pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
     pub copy_instead_paths: Vec<String>,
     pub remove_paths: Vec<String>,
     pub dll_overrides: Vec<String>,
+    pub unique_prefix: bool,
+    pub prefix_setup: Vec<String>,
 
     pub path_goldberg: String,
     pub steam_appid: Option<String>,
     pub coldclient: bool,
 
+    // Ids used to find the game in other launchers' libraries
+    pub gog_id: Option<String>,
+    pub epic_appname: Option<String>,
+    pub lutris_slug: Option<String>,
+    pub bottles_program: Option<String>,
+    pub itch_id: Option<String>,
+
+    // Scripts in the handler folder, run with the game folder as working directory. Every hook gets
+    // GAMEDIR and PLAYER_COUNT in its environment; hooks.per_instance_pre also gets PROFILE and
+    // PLAYER_INDEX. With hooks.background the launch hooks aren't waited for and keep running until
+    // the session ends.
+    pub hook_pre_launch: String,
+    pub hook_per_instance_pre: String,
+    pub hook_post_session: String,
+    pub hooks_background: bool,
+
     pub win_unique_appdata: bool,
     pub win_unique_documents: bool,
     pub linux_unique_localshare: bool,
     pub linux_unique_config: bool,
     pub game_unique_paths: Vec<String>,
+
+    // Alternative editions of the game, as complete handler JSON
+    pub variants: Vec<Value>,
 }
 
 impl Handler {
     pub fn new(json_path: &PathBuf) -> Result<Self, Box<dyn Error>> {
-        let file = File::open(json_path)?;
-        let reader = BufReader::new(file);
-        let json: Value = serde_json::from_reader(reader)?;
+        let json = read_handler_json(json_path, &mut Vec::new())?;
+        let path_handler = json_path
+            .parent()
+            .ok_or_else(|| "Invalid path")?
+            .to_path_buf();
+
+        let mut handler = Self::from_json(&json, &path_handler)?;
+        handler.base_dirs = handler_base_dirs(json_path);
+        if !handler.base_dirs.is_empty() {
+            handler.img_paths = handler.get_imgs();
+        }
+        Ok(handler)
+    }
+
+    // A file or folder from the handler folder, such as a hook script, templates/ or icon.png.
+    // Handlers using handler.extends fall back to the assets of the handler they extend.
+    pub fn asset(&self, rel_path: &str) -> PathBuf {
+        std::iter::once(&self.path_handler)
+            .chain(&self.base_dirs)
+            .map(|dir| dir.join(rel_path))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| self.path_handler.join(rel_path))
+    }
 
+    pub fn from_json(json: &Value, path_handler: &PathBuf) -> Result<Self, Box<dyn Error>> {
         let mut handler = Self {
-            path_handler: PathBuf::new(),
+            path_handler: path_handler.clone(),
+            root: None,
+            base_dirs: Vec::new(),
             img_paths: Vec::new(),
 
             uid: json["handler.uid"].as_str().unwrap_or_default().to_string(),
@@ -71,6 +125,9 @@ impl Handler {
             symlink_dir: json["game.symlink_dir"].as_bool().unwrap_or_default(),
             win: json["game.win"].as_bool().unwrap_or_default(),
             is32bit: json["game.32bit"].as_bool().unwrap_or_default(),
+            max_players: json_id(&json["game.max_players"])
+                .and_then(|n| n.parse().ok())
+                .unwrap_or_default(),
             runtime: json["game.runtime"]
                 .as_str()
                 .unwrap_or_default()
@@ -88,6 +145,14 @@ impl Handler {
                         .collect()
                 })
                 .unwrap_or_default(),
+            env: json["game.env"]
+                .as_object()
+                .map(|obj| {
+                    obj.iter()
+                        .map(|(k, v)| (k.to_string(), v.as_str().unwrap_or_default().to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
             copy_instead_paths: json["game.copy_instead_paths"]
                 .as_array()
                 .map(|arr| {
@@ -112,6 +177,16 @@ impl Handler {
                         .collect()
                 })
                 .unwrap_or_default(),
+            unique_prefix: json["game.unique_prefix"].as_bool().unwrap_or_default(),
+            prefix_setup: json["game.prefix_setup"]
+                .as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .map(|v| v.as_str().unwrap_or_default().to_string().sanitize_path())
+                        .filter(|v| !v.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
 
             path_goldberg: json["steam.api_path"]
                 .as_str()
@@ -123,6 +198,29 @@ impl Handler {
                 .and_then(|s| Some(s.to_string())),
             coldclient: json["steam.gb_coldclient"].as_bool().unwrap_or_default(),
 
+            gog_id: json_id(&json["gog.id"]),
+            epic_appname: json_id(&json["epic.appname"]),
+            lutris_slug: json_id(&json["lutris.slug"]),
+            bottles_program: json_id(&json["bottles.program"]),
+            itch_id: json_id(&json["itch.id"]),
+
+            hook_pre_launch: json["hooks.pre_launch"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+                .sanitize_path(),
+            hook_per_instance_pre: json["hooks.per_instance_pre"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+                .sanitize_path(),
+            hook_post_session: json["hooks.post_session"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+                .sanitize_path(),
+            hooks_background: json["hooks.background"].as_bool().unwrap_or_default(),
+
             win_unique_appdata: json["profiles.unique_appdata"]
                 .as_bool()
                 .unwrap_or_default(),
@@ -141,21 +239,130 @@ impl Handler {
                         .collect()
                 })
                 .unwrap_or_default(),
+
+            variants: Vec::new(),
         };
 
         if !handler.uid.chars().all(char::is_alphanumeric) {
             return Err("uid must be alphanumeric!".into());
         }
 
-        handler.path_handler = json_path
-            .parent()
-            .ok_or_else(|| "Invalid path")?
-            .to_path_buf();
+        // Each variant is kept as the full handler JSON with the variant's fields applied
+        if let Some(variants) = json["variants"].as_array() {
+            let mut base = json.clone();
+            if let Some(obj) = base.as_object_mut() {
+                obj.remove("variants");
+            }
+            for variant in variants {
+                let Some(fields) = variant.as_object() else {
+                    return Err("variants must only contain objects".into());
+                };
+                let mut merged = base.clone();
+                for (key, value) in fields {
+                    merged[key] = value.clone();
+                }
+                handler.variants.push(merged);
+            }
+        }
+
         handler.img_paths = handler.get_imgs();
 
         Ok(handler)
     }
 
+    // Whether path_root holds the game: its executable, or a variant's, exists there.
+    // Templated executables can't be checked without an instance, so they're assumed present.
+    pub fn is_installed_in(&self, path_root: &Path) -> bool {
+        if !path_root.is_dir() {
+            return false;
+        }
+        if self.exec.is_empty() || self.exec.contains('$') || path_root.join(&self.exec).exists() {
+            return true;
+        }
+        self.variants.iter().any(|variant| {
+            let exec = variant["game.exec"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+                .sanitize_path();
+            !exec.is_empty() && path_root.join(exec).exists()
+        })
+    }
+
+    // Picks the edition of the game installed in path_root: the handler itself if its executable exists,
+    // otherwise the first variant whose game.exec does
+    pub fn select_variant(&self, path_root: &PathBuf) -> Handler {
+        if self.variants.is_empty() || path_root.join(&self.exec).exists() {
+            return self.clone();
+        }
+        for variant in &self.variants {
+            let exec = variant["game.exec"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+                .sanitize_path();
+            if !exec.is_empty()
+                && path_root.join(&exec).exists()
+                && let Ok(mut handler) = Handler::from_json(variant, &self.path_handler)
+            {
+                handler.base_dirs = self.base_dirs.clone();
+                handler.img_paths = handler.get_imgs();
+                return handler;
+            }
+        }
+        self.clone()
+    }
+
+    // The handler's fields as handler.json keys, the inverse of from_json. Variants aren't included.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "handler.uid": self.uid,
+            "handler.name": self.name,
+            "handler.author": self.author,
+            "handler.version": self.version,
+            "handler.info": self.info,
+
+            "game.symlink_dir": self.symlink_dir,
+            "game.win": self.win,
+            "game.32bit": self.is32bit,
+            "game.max_players": self.max_players,
+            "game.runtime": self.runtime,
+            "game.exec": self.exec,
+            "game.args": self.args,
+            "game.env": self
+                .env
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+                .collect::<serde_json::Map<String, Value>>(),
+            "game.copy_instead_paths": self.copy_instead_paths,
+            "game.remove_paths": self.remove_paths,
+            "game.dll_overrides": self.dll_overrides,
+            "game.unique_prefix": self.unique_prefix,
+            "game.prefix_setup": self.prefix_setup,
+
+            "steam.api_path": self.path_goldberg,
+            "steam.appid": self.steam_appid,
+            "steam.gb_coldclient": self.coldclient,
+
+            "gog.id": self.gog_id,
+            "epic.appname": self.epic_appname,
+            "lutris.slug": self.lutris_slug,
+            "bottles.program": self.bottles_program,
+            "itch.id": self.itch_id,
+
+            "hooks.pre_launch": self.hook_pre_launch,
+            "hooks.per_instance_pre": self.hook_per_instance_pre,
+            "hooks.post_session": self.hook_post_session,
+            "hooks.background": self.hooks_background,
+
+            "profiles.unique_appdata": self.win_unique_appdata,
+            "profiles.unique_documents": self.win_unique_documents,
+            "profiles.unique_localshare": self.linux_unique_localshare,
+            "profiles.unique_config": self.linux_unique_config,
+            "profiles.game_paths": self.game_unique_paths,
+        })
+    }
+
     pub fn display(&self) -> &str {
         if self.name.is_empty() {
             self.uid.as_str()
@@ -164,9 +371,112 @@ impl Handler {
         }
     }
 
+    // Checks the fields a handler can't work without
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        match self.validation_errors().into_iter().next() {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    // Every problem that stops the handler from being installed or launched; also reported by lint
+    pub fn validation_errors(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.uid.is_empty() {
+            out.push("handler.uid is missing".to_string());
+        } else if !self.uid.chars().all(char::is_alphanumeric) {
+            out.push(format!(
+                "handler.uid \"{}\" must only contain letters and numbers",
+                self.uid
+            ));
+        }
+        if self.exec.is_empty() {
+            out.push("game.exec is not set".to_string());
+        }
+        if !["", "scout", "soldier"].contains(&self.runtime.as_str()) {
+            out.push(format!(
+                "game.runtime \"{}\" is unknown; expected \"scout\", \"soldier\" or nothing",
+                self.runtime
+            ));
+        }
+        out
+    }
+
+    // Describes what the handler adds to the game, for the prompt shown when installing unsigned handlers
+    pub fn untrusted_summary(&self) -> String {
+        let mut out = String::new();
+
+        let path_copy = self.asset("copy_to_symdir");
+        let files: Vec<String> = walkdir::WalkDir::new(&path_copy)
+            .min_depth(1)
+            .into_iter()
+            .flatten()
+            .filter(|entry| !entry.file_type().is_dir())
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(&path_copy)
+                    .ok()
+                    .map(|p| p.display().to_string())
+            })
+            .collect();
+        if !files.is_empty() {
+            out.push_str("Files added to the game folder:\n");
+            for file in files.iter().take(15) {
+                out.push_str(&format!("  {file}\n"));
+            }
+            if files.len() > 15 {
+                out.push_str(&format!("  ...and {} more\n", files.len() - 15));
+            }
+        }
+        if !self.args.is_empty() {
+            out.push_str(&format!("Launch arguments: {}\n", self.args.join(" ")));
+        }
+        if !self.env.is_empty() {
+            let env: Vec<String> = self.env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            out.push_str(&format!("Environment: {}\n", env.join(" ")));
+        }
+        let hooks: Vec<&str> = self.hooks().into_iter().map(|(_, script)| script).collect();
+        if !hooks.is_empty() {
+            out.push_str(&format!("Scripts run at launch: {}\n", hooks.join(", ")));
+        }
+        if !self.prefix_setup.is_empty() {
+            out.push_str(&format!("Prefix setup: {}\n", self.prefix_setup.join(", ")));
+        }
+        if out.is_empty() {
+            out.push_str(
+                "The handler doesn't add files, launch arguments or environment variables.\n",
+            );
+        }
+        out
+    }
+
+    // The hook scripts the handler sets, by their handler.json key
+    pub fn hooks(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("hooks.pre_launch", self.hook_pre_launch.as_str()),
+            (
+                "hooks.per_instance_pre",
+                self.hook_per_instance_pre.as_str(),
+            ),
+            ("hooks.post_session", self.hook_post_session.as_str()),
+        ]
+        .into_iter()
+        .filter(|(_, script)| !script.is_empty())
+        .collect()
+    }
+
+    // Games get their own prefix in pfx/<uid> if the handler asks for it or the user enabled it globally
+    pub fn path_prefix(&self, cfg: &PartyConfig) -> PathBuf {
+        match self.unique_prefix || cfg.per_game_prefix {
+            true => PATH_PARTY.join("pfx").join(&self.uid),
+            false => PATH_PARTY.join("pfx"),
+        }
+    }
+
     fn get_imgs(&self) -> Vec<PathBuf> {
         let mut out = Vec::new();
-        let imgs_path = self.path_handler.join("imgs");
+        let imgs_path = self.asset("imgs");
 
         let entries = match std::fs::read_dir(imgs_path) {
             Ok(entries) => entries,
@@ -197,6 +507,100 @@ impl Handler {
     }
 }
 
+// Launcher ids may be written as strings or numbers
+fn json_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+// Reads handler.json, merging it over the handler it extends, if any. The extending handler's fields win.
+// chain holds the uids already being resolved, to catch handlers that extend each other.
+pub fn read_handler_json(
+    json_path: &PathBuf,
+    chain: &mut Vec<String>,
+) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(json_path)?;
+    let json: Value = serde_json::from_reader(BufReader::new(file))?;
+
+    let Some(mut merged) = read_extended_json(&json, chain)? else {
+        return Ok(json);
+    };
+    if let Some(fields) = json.as_object() {
+        for (key, value) in fields {
+            merged[key] = value.clone();
+        }
+    }
+    if let Some(obj) = merged.as_object_mut() {
+        obj.remove("handler.extends");
+    }
+    Ok(merged)
+}
+
+// The resolved JSON of the handler that json extends through handler.extends, if it extends one
+pub fn read_extended_json(
+    json: &Value,
+    chain: &mut Vec<String>,
+) -> Result<Option<Value>, Box<dyn Error>> {
+    let Some(base_uid) = json["handler.extends"].as_str() else {
+        return Ok(None);
+    };
+
+    chain.push(json["handler.uid"].as_str().unwrap_or_default().to_string());
+    if chain.iter().any(|uid| uid == base_uid) {
+        return Err(format!(
+            "handler.extends loops: {} -> {base_uid}",
+            chain.join(" -> ")
+        )
+        .into());
+    }
+    let base_path = find_handler_json(base_uid).ok_or_else(|| {
+        format!("Handler {base_uid}, which this handler extends, is not installed")
+    })?;
+    Ok(Some(read_handler_json(&base_path, chain)?))
+}
+
+// Folders of the handlers that json_path extends, directly or through other handlers, nearest first
+fn handler_base_dirs(json_path: &Path) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    let mut path = json_path.to_path_buf();
+    while let Ok(file) = File::open(&path)
+        && let Ok(json) = serde_json::from_reader::<_, Value>(BufReader::new(file))
+        && let Some(base_uid) = json["handler.extends"].as_str()
+        && let Some(base_path) = find_handler_json(base_uid)
+        && let Some(dir) = base_path.parent()
+    {
+        // Loops are reported by read_handler_json
+        if out.iter().any(|d| d == dir) || json_path.parent() == Some(dir) {
+            break;
+        }
+        out.push(dir.to_path_buf());
+        path = base_path;
+    }
+    out
+}
+
+// Finds the handler.json of an installed handler, which is normally in a folder named after its uid
+fn find_handler_json(uid: &str) -> Option<PathBuf> {
+    let handlers_path = PATH_PARTY.join("handlers");
+    let uid_of = |path: &PathBuf| -> Option<String> {
+        let json: Value = serde_json::from_reader(BufReader::new(File::open(path).ok()?)).ok()?;
+        Some(json["handler.uid"].as_str()?.to_string())
+    };
+
+    let path = handlers_path.join(uid).join("handler.json");
+    if uid_of(&path).as_deref() == Some(uid) {
+        return Some(path);
+    }
+    std::fs::read_dir(handlers_path)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path().join("handler.json"))
+        .find(|path| uid_of(path).as_deref() == Some(uid))
+}
+
 pub fn scan_handlers() -> Vec<Handler> {
     let mut out: Vec<Handler> = Vec::new();
     let handlers_path = PATH_PARTY.join("handlers");
@@ -215,55 +619,258 @@ pub fn scan_handlers() -> Vec<Handler> {
             Ok(ft) => ft,
             Err(_) => continue,
         };
-        if !file_type.is_dir() {
+        if !file_type.is_dir() || entry.file_name().to_string_lossy().starts_with('.') {
             continue;
         }
         let json_path = entry.path().join("handler.json");
         if !json_path.exists() {
             continue;
         }
-        if let Ok(handler) = Handler::new(&json_path) {
-            out.push(handler);
+        match Handler::new(&json_path) {
+            // Show the edition that's installed if it can be found without asking
+            Ok(handler) if !handler.variants.is_empty() => match peek_rootpath_handler(&handler) {
+                Some(root) => out.push(handler.select_variant(&PathBuf::from(root))),
+                None => out.push(handler),
+            },
+            Ok(handler) => out.push(handler),
+            Err(err) => println!("Skipping handler {}: {err}", entry.path().display()),
         }
     }
     out.sort_by(|a, b| a.display().to_lowercase().cmp(&b.display().to_lowercase()));
     out
 }
 
-pub fn install_handler_from_file(file: &PathBuf) -> Result<(), Box<dyn Error>> {
+// Handler archives bigger than this once extracted are rejected
+const HANDLER_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+// Extracts a .pdh archive, refusing entries that would end up outside dest, symlinks,
+// and archives that extract to more than HANDLER_MAX_SIZE bytes.
+pub fn extract_handler_archive(file: &PathBuf, dest: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(File::open(file)?)?;
+    let mut total_size: u64 = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let rel_path = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("Unsafe path in archive: {}", entry.name()))?;
+        if entry.is_symlink() {
+            return Err(format!("Symlinks aren't allowed in handlers: {}", entry.name()).into());
+        }
+
+        let out_path = dest.join(rel_path);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Count the bytes actually written, since sizes in the archive's headers can lie
+        let remaining = HANDLER_MAX_SIZE - total_size;
+        let mut out_file = File::create(&out_path)?;
+        let written = std::io::copy(&mut (&mut entry).take(remaining + 1), &mut out_file)?;
+        if written > remaining {
+            return Err("Handler archive is too large".into());
+        }
+        total_size += written;
+
+        if let Some(mode) = entry.unix_mode() {
+            std::fs::set_permissions(&out_path, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+
+    Ok(())
+}
+
+// expected_uid rejects archives of other handlers, e.g. when installing from the catalogue
+pub fn install_handler_from_file(
+    file: &PathBuf,
+    expected_uid: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
     if !file.exists() || !file.is_file() || file.extension().unwrap_or_default() != "pdh" {
         return Err("Handler not valid!".into());
     }
 
-    let dir_handlers = PATH_PARTY.join("handlers");
-    let dir_tmp = PATH_PARTY.join("tmp");
-    if !dir_tmp.exists() {
-        std::fs::create_dir_all(&dir_tmp)?;
+    // Each install gets its own temp dir inside PATH_PARTY, so the final rename stays on the same filesystem
+    let dir_tmp = PATH_PARTY
+        .join("tmp")
+        .join(format!("install-{}", fastrand::u32(..)));
+    std::fs::create_dir_all(&dir_tmp)?;
+
+    let result = install_extracted_handler(file, &dir_tmp, expected_uid);
+    if dir_tmp.exists() {
+        let _ = std::fs::remove_dir_all(&dir_tmp);
     }
+    result
+}
 
-    let mut archive = zip::ZipArchive::new(File::open(&file)?)?;
-    archive.extract(&dir_tmp)?;
+fn install_extracted_handler(
+    file: &PathBuf,
+    dir_tmp: &PathBuf,
+    expected_uid: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    extract_handler_archive(file, dir_tmp)?;
 
     let handler_path = dir_tmp.join("handler.json");
     if !handler_path.exists() {
         return Err("handler.json not found in archive".into());
     }
+    let handler = Handler::new(&handler_path)?;
+    handler.validate()?;
+    if let Some(expected_uid) = expected_uid
+        && handler.uid != expected_uid
+    {
+        return Err(format!(
+            "Archive contains handler \"{}\" instead of \"{expected_uid}\"",
+            handler.uid
+        )
+        .into());
+    }
+
+    match verify_handler(dir_tmp)? {
+        SignatureStatus::Invalid => {
+            return Err("Handler signature doesn't match its contents".into());
+        }
+        SignatureStatus::Trusted(name) => {
+            println!("Handler {} is signed by {name}", handler.uid);
+        }
+        status => {
+            let signer = match status {
+                SignatureStatus::UnknownSigner(key) => format!("signed by an unknown key:\n{key}"),
+                _ => "not signed".to_string(),
+            };
+            if !yesno(
+                "Untrusted handler",
+                &format!(
+                    "{} is {signer}\n\n{}\nHandlers can run programs in your session. Only install handlers from people you trust. Continue?",
+                    handler.display(),
+                    handler.untrusted_summary()
+                ),
+            ) {
+                println!("Cancelled installing untrusted handler {}", handler.uid);
+                return Ok(());
+            }
+        }
+    }
+
+    let dir_handlers = PATH_PARTY.join("handlers");
+    let dest = dir_handlers.join(&handler.uid);
+
+    if !dest.exists() {
+        std::fs::rename(dir_tmp, &dest)?;
+        return Ok(());
+    }
+
+    let installed_version = match Handler::new(&dest.join("handler.json")) {
+        Ok(installed) => installed.version,
+        Err(_) => "unknown".to_string(),
+    };
+    if !yesno(
+        "Replace handler?",
+        &format!(
+            "{} is already installed.\n\nInstalled version: {}\nNew version: {}\n\nReplace it?",
+            handler.display(),
+            installed_version,
+            handler.version
+        ),
+    ) {
+        println!("Kept installed handler {}", handler.uid);
+        return Ok(());
+    }
 
-    let handler_file = File::open(handler_path)?;
-    let handler_json: Value = serde_json::from_reader(BufReader::new(handler_file))?;
+    // Swap the folders with renames so a failed install never leaves a mix of old and new files.
+    // The old copy is hidden with a leading dot so scan_handlers ignores it.
+    let dest_old = dir_handlers.join(format!(".{}-old", handler.uid));
+    if dest_old.exists() {
+        std::fs::remove_dir_all(&dest_old)?;
+    }
+    std::fs::rename(&dest, &dest_old)?;
+    if let Err(err) = std::fs::rename(dir_tmp, &dest) {
+        std::fs::rename(&dest_old, &dest)?;
+        return Err(err.into());
+    }
+    std::fs::remove_dir_all(&dest_old)?;
 
-    let uid = handler_json
-        .get("handler.uid")
-        .and_then(|v| v.as_str())
-        .ok_or("No uid field found in handler.json")?;
+    Ok(())
+}
 
-    if !uid.chars().all(char::is_alphanumeric) {
-        return Err("uid must be alphanumeric".into());
+// Packs an installed handler folder back into a .pdh archive named <uid>-<version>.pdh
+pub fn export_handler(h: &Handler, dest_dir: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
+    // Re-read handler.json so edits made since PartyDeck started are validated too
+    let handler = Handler::new(&h.path_handler.join("handler.json"))?;
+    handler.validate()?;
+
+    let filename = match handler.version.is_empty() {
+        true => format!("{}.pdh", handler.uid),
+        false => format!(
+            "{}-{}.pdh",
+            handler.uid,
+            handler.version.sanitize_path().replace('/', "_")
+        ),
+    };
+    // Compare canonical paths, so the output is recognised if it's inside the handler folder
+    let path_handler = handler.path_handler.canonicalize()?;
+    let dest_dir = dest_dir.canonicalize()?;
+    let path_pdh = dest_dir.join(&filename);
+    // The archive is written next to its final location and only renamed once it's complete
+    let path_tmp = dest_dir.join(format!(".{filename}.tmp"));
+    let skip = [path_pdh.clone(), path_tmp.clone()];
+
+    if let Err(err) = write_handler_archive(&path_handler, &path_tmp, &skip) {
+        let _ = std::fs::remove_file(&path_tmp);
+        return Err(err);
     }
+    std::fs::rename(&path_tmp, &path_pdh)?;
 
-    copy_dir_recursive(&dir_tmp, &dir_handlers.join(uid), false, true)?;
-    std::fs::remove_dir_all(&dir_tmp)?;
+    println!("Exported handler {} to {}", handler.uid, path_pdh.display());
+    Ok(path_pdh)
+}
+
+fn write_handler_archive(
+    path_handler: &PathBuf,
+    path_out: &PathBuf,
+    skip: &[PathBuf],
+) -> Result<(), Box<dyn Error>> {
+    let mut archive = zip::ZipWriter::new(File::create(path_out)?);
+
+    for entry in walkdir::WalkDir::new(path_handler).min_depth(1) {
+        let entry = entry?;
+        if skip.iter().any(|p| p == entry.path()) {
+            continue;
+        }
+        // Symlinks could pull in files from outside the handler folder
+        if entry.file_type().is_symlink() {
+            println!("Skipping symlink {}", entry.path().display());
+            continue;
+        }
+        let rel_path = entry
+            .path()
+            .strip_prefix(path_handler)?
+            .to_string_lossy()
+            .to_string();
+
+        // Keep permissions so binaries in copy_to_symdir stay executable
+        let mode = entry.metadata()?.permissions().mode();
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(mode);
+
+        if entry.file_type().is_dir() {
+            archive.add_directory(rel_path, options)?;
+        } else if rel_path != SIGNATURE_FILE {
+            archive.start_file(rel_path, options)?;
+            std::io::copy(&mut File::open(entry.path())?, &mut archive)?;
+        }
+    }
 
+    // Sign the handler if the user has generated a signing key
+    if let Some(key) = load_signing_key() {
+        archive.start_file(SIGNATURE_FILE, zip::write::SimpleFileOptions::default())?;
+        archive.write_all(sign_handler(path_handler, &key, skip)?.as_bytes())?;
+    }
+    archive.finish()?;
     Ok(())
 }
 
@@ -306,7 +913,7 @@ pub fn create_symlink_folder(h: &Handler) -> Result<(), Box<dyn Error>> {
             std::fs::remove_file(p)?;
         }
     }
-    let copypath = PathBuf::from(&h.path_handler).join("copy_to_symdir");
+    let copypath = h.asset("copy_to_symdir");
     if copypath.exists() {
         copy_dir_recursive(&copypath, &path_sym, false, true)?;
     }
@@ -0,0 +1,162 @@
+use crate::input::{DeviceInfo, DeviceType, Instance};
+use crate::paths::*;
+use crate::util::{lock_file, write_atomic};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// One play session, stored as a line of history.jsonl
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionRecord {
+    pub uid: String,
+    // Unix time in seconds
+    pub start: u64,
+    pub end: u64,
+    pub duration: u64,
+    // Profile of each instance
+    pub profiles: Vec<String>,
+    // Types of the devices of each instance, e.g. ["Gamepad"] or ["Keyboard", "Mouse"]
+    pub devices: Vec<Vec<String>>,
+    // Exit code of the session, None if it was killed by a signal
+    pub exit_status: Option<i32>,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl SessionRecord {
+    pub fn new(
+        uid: &str,
+        start: u64,
+        instances: &[Instance],
+        input_devices: &[DeviceInfo],
+        exit_status: Option<i32>,
+    ) -> Self {
+        let end = unix_now();
+        Self {
+            uid: uid.to_string(),
+            start,
+            end,
+            duration: end.saturating_sub(start),
+            profiles: instances.iter().map(|i| i.profname.clone()).collect(),
+            devices: instances
+                .iter()
+                .map(|i| {
+                    i.devices
+                        .iter()
+                        .map(|&d| device_type_name(input_devices[d].device_type).to_string())
+                        .collect()
+                })
+                .collect(),
+            exit_status,
+        }
+    }
+}
+
+pub fn device_type_name(device_type: DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Gamepad => "Gamepad",
+        DeviceType::Keyboard => "Keyboard",
+        DeviceType::Mouse => "Mouse",
+        DeviceType::Other => "Other",
+    }
+}
+
+fn path_history() -> PathBuf {
+    PATH_PARTY.join("history.jsonl")
+}
+
+pub fn append_session(record: &SessionRecord) -> Result<(), Box<dyn Error>> {
+    let path = path_history();
+    // Locked so the session can't be appended to a file rename_history_game is about to replace
+    let _lock = lock_file(&path, true)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+// Every recorded session, oldest first. Lines that can't be read are skipped.
+pub fn load_history() -> Vec<SessionRecord> {
+    let path = path_history();
+    let _lock = lock_file(&path, false);
+    let Ok(file) = File::open(&path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+// Gives a game's sessions a new uid. Lines that can't be read are kept as they are.
+pub fn rename_history_game(old_uid: &str, new_uid: &str) -> Result<(), Box<dyn Error>> {
+    let path = path_history();
+    let _lock = lock_file(&path, true)?;
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+
+    let mut out = String::new();
+    for line in contents.lines() {
+        match serde_json::from_str::<SessionRecord>(line) {
+            Ok(mut record) if record.uid == old_uid => {
+                record.uid = new_uid.to_string();
+                out.push_str(&serde_json::to_string(&record)?);
+            }
+            _ => out.push_str(line),
+        }
+        out.push('\n');
+    }
+
+    write_atomic(&path, out.as_bytes(), true)
+}
+
+// Seconds played by the profile, by game uid
+pub fn profile_playtime(history: &[SessionRecord], profile: &str) -> BTreeMap<String, u64> {
+    let mut out = BTreeMap::new();
+    for record in history
+        .iter()
+        .filter(|r| r.profiles.iter().any(|p| p == profile))
+    {
+        *out.entry(record.uid.clone()).or_default() += record.duration;
+    }
+    out
+}
+
+pub fn last_session<'a>(history: &'a [SessionRecord], uid: &str) -> Option<&'a SessionRecord> {
+    history.iter().rev().find(|r| r.uid == uid)
+}
+
+// e.g. "2h 5m", or "40s" for short sessions
+pub fn format_duration(secs: u64) -> String {
+    match (secs / 3600, secs / 60 % 60) {
+        (0, 0) => format!("{secs}s"),
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+// e.g. "5 minutes ago" or "3 days ago"
+pub fn format_ago(time: u64) -> String {
+    let secs = unix_now().saturating_sub(time);
+    let (n, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    match n {
+        1 => format!("1 {unit} ago"),
+        _ => format!("{n} {unit}s ago"),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::handler::Handler;
+use crate::paths::*;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Hooks still running after this long are killed, unless the handler runs them in the background
+const HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Hook output and the launch command of the last session, overwritten on every launch
+pub fn open_session_log() -> Result<File, Box<dyn Error>> {
+    Ok(File::create(PATH_PARTY.join("session.log"))?)
+}
+
+// Hooks started with hooks.background. They are killed when this is dropped, once the session is over.
+#[derive(Default)]
+pub struct BackgroundHooks(Vec<Child>);
+
+impl Drop for BackgroundHooks {
+    fn drop(&mut self) {
+        for child in &mut self.0 {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// Starts a hook script from the handler folder, with the game folder as working directory.
+// Every hook gets GAMEDIR and PLAYER_COUNT; per-instance hooks also get PROFILE and PLAYER_INDEX.
+fn spawn_hook(
+    h: &Handler,
+    key: &str,
+    script: &str,
+    gamedir: &str,
+    env: &[(&str, String)],
+    log: &mut File,
+) -> Result<Option<Child>, Box<dyn Error>> {
+    if script.is_empty() {
+        return Ok(None);
+    }
+    let path = h.asset(script);
+    if !path.is_file() {
+        return Err(format!("{key} script not found: {}", path.display()).into());
+    }
+
+    println!("Running {key} hook {script}");
+    writeln!(log, "== {key}: {script}")?;
+    log.flush()?;
+
+    let child = Command::new(&path)
+        .current_dir(gamedir)
+        .env("GAMEDIR", gamedir)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log.try_clone()?)
+        .spawn()?;
+    Ok(Some(child))
+}
+
+// Runs a hook and waits for it to finish, killing it after HOOK_TIMEOUT
+pub fn run_hook(
+    h: &Handler,
+    key: &str,
+    script: &str,
+    gamedir: &str,
+    env: &[(&str, String)],
+    log: &mut File,
+) -> Result<(), Box<dyn Error>> {
+    let Some(mut child) = spawn_hook(h, key, script, gamedir, env, log)? else {
+        return Ok(());
+    };
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > HOOK_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            writeln!(log, "== {key} timed out")?;
+            return Err(format!(
+                "{key} hook {script} timed out after {} seconds",
+                HOOK_TIMEOUT.as_secs()
+            )
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    writeln!(log, "== {key} exited with {status}")?;
+    if !status.success() {
+        return Err(format!(
+            "{key} hook {script} failed ({status}), see {}",
+            PATH_PARTY.join("session.log").display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// Runs a hook that comes before the game starts. If the handler sets hooks.background it isn't
+// waited for, and keeps running until background is dropped.
+pub fn run_launch_hook(
+    h: &Handler,
+    key: &str,
+    script: &str,
+    gamedir: &str,
+    env: &[(&str, String)],
+    log: &mut File,
+    background: &mut BackgroundHooks,
+) -> Result<(), Box<dyn Error>> {
+    match h.hooks_background {
+        true => {
+            background
+                .0
+                .extend(spawn_hook(h, key, script, gamedir, env, log)?);
+            Ok(())
+        }
+        false => run_hook(h, key, script, gamedir, env, log),
+    }
+}
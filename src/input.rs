@@ -94,6 +94,14 @@ impl InputDevice {
     pub fn has_button_held(&self) -> bool {
         self.has_button_held
     }
+    pub fn info(&self) -> DeviceInfo {
+        DeviceInfo {
+            path: self.path.clone(),
+            vendor: self.vendor(),
+            enabled: self.enabled,
+            device_type: self.device_type,
+        }
+    }
     pub fn poll(&mut self) -> Option<PadButton> {
         let mut btn: Option<PadButton> = None;
         if let Some(dev) = &mut self.dev {
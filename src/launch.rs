@@ -1,10 +1,14 @@
+use std::io::Write;
 use std::path::PathBuf;
 
-use crate::app::PartyConfig;
+use crate::app::{GameOverrides, PartyConfig, load_overrides};
 use crate::game::Game;
 use crate::handler::*;
+use crate::history::*;
+use crate::hooks::*;
 use crate::input::*;
 use crate::launch::Game::{Executable, HandlerRef};
+use crate::library::record_play;
 use crate::paths::*;
 use crate::util::*;
 
@@ -13,20 +17,68 @@ pub fn launch_game(
     input_devices: &[DeviceInfo],
     instances: &Vec<Instance>,
     cfg: &PartyConfig,
+    progress: &dyn Fn(&str),
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if let HandlerRef(h) = game {
-        for instance in instances {
-            create_profile(instance.profname.as_str())?;
-            create_gamesave(instance.profname.as_str(), &h)?;
-        }
-        if h.symlink_dir {
-            create_symlink_folder(&h)?;
+    let h = &match game {
+        // The generated handler knows its folder, so nothing is saved to paths.json
+        Executable(entry) => entry.to_handler(),
+        // Handlers with variants weren't resolved during the scan if the game's folder wasn't known yet
+        HandlerRef(h) if !h.variants.is_empty() => {
+            h.select_variant(&PathBuf::from(get_rootpath_handler(h)?))
         }
+        HandlerRef(h) => h.clone(),
+    };
+
+    let overrides = load_overrides(&h.uid);
+    let cfg = &overrides.apply(cfg);
+
+    for instance in instances {
+        create_profile(instance.profname.as_str())?;
+        create_gamesave(instance.profname.as_str(), h)?;
+    }
+    if h.symlink_dir {
+        create_symlink_folder(h)?;
+    }
+    prepare_instance_saves(h, input_devices, instances, cfg)?;
+    if h.win {
+        std::fs::create_dir_all(h.path_prefix(cfg))?;
+        apply_prefix_setup(h, cfg, progress)?;
+        progress("");
     }
 
-    let cmd = launch_cmd(game, input_devices, instances, cfg)?;
+    let cmd = launch_cmd(h, input_devices, instances, cfg, &overrides)?;
     println!("\nCOMMAND:\n{}\n", cmd);
 
+    let mut log = open_session_log()?;
+    let player_count = instances.len().to_string();
+    let gamedir = handler_gamedir(h)?;
+    let mut background_hooks = BackgroundHooks::default();
+    run_launch_hook(
+        h,
+        "hooks.pre_launch",
+        &h.hook_pre_launch,
+        &gamedir,
+        &[("PLAYER_COUNT", player_count.clone())],
+        &mut log,
+        &mut background_hooks,
+    )?;
+    for (i, instance) in instances.iter().enumerate() {
+        run_launch_hook(
+            h,
+            "hooks.per_instance_pre",
+            &h.hook_per_instance_pre,
+            &gamedir,
+            &[
+                ("PLAYER_COUNT", player_count.clone()),
+                ("PROFILE", instance.profname.clone()),
+                ("PLAYER_INDEX", i.to_string()),
+            ],
+            &mut log,
+            &mut background_hooks,
+        )?;
+    }
+    writeln!(log, "== COMMAND: {cmd}")?;
+
     if cfg.enable_kwin_script {
         let script = if instances.len() == 2 && cfg.vertical_two_player {
             "splitscreen_kwin_vertical.js"
@@ -37,46 +89,61 @@ pub fn launch_game(
         kwin_dbus_start_script(PATH_RES.join(script))?;
     }
 
-    std::process::Command::new("sh")
+    if let Err(err) = record_play(&h.uid) {
+        println!("Couldn't update library.json: {err}");
+    }
+
+    let start = unix_now();
+    let status = std::process::Command::new("sh")
         .arg("-c")
         .arg(cmd)
         .status()?;
 
+    let record = SessionRecord::new(&h.uid, start, instances, input_devices, status.code());
+    if let Err(err) = append_session(&record) {
+        println!("Couldn't update history.jsonl: {err}");
+    }
+
+    // Background hooks only last as long as the session
+    drop(background_hooks);
+
     if cfg.enable_kwin_script {
         kwin_dbus_unload_script()?;
     }
 
+    // The session is already over, so a failing cleanup hook is only reported
+    if let Err(err) = run_hook(
+        h,
+        "hooks.post_session",
+        &h.hook_post_session,
+        &handler_gamedir(h)?,
+        &[("PLAYER_COUNT", player_count)],
+        &mut log,
+    ) {
+        println!("{err}");
+    }
+
     remove_guest_profiles()?;
 
     Ok(())
 }
 
 pub fn launch_cmd(
-    game: &Game,
+    h: &Handler,
     input_devices: &[DeviceInfo],
     instances: &Vec<Instance>,
     cfg: &PartyConfig,
+    overrides: &GameOverrides,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let home = PATH_HOME.display();
     let localshare = PATH_LOCAL_SHARE.display();
-    let party = PATH_PARTY.display();
     let steam = PATH_STEAM.display();
     let res = PATH_RES.display();
 
     let mut gsc_lowres_warn = true;
 
-    let gamedir = match game {
-        Executable { .. } => "",
-        HandlerRef(h) => match h.symlink_dir {
-            true => &format!("{party}/gamesyms/{}", h.uid),
-            false => &get_rootpath_handler(&h)?,
-        },
-    };
-
-    let win = match game {
-        Executable { path, .. } => path.extension().unwrap_or_default() == "exe",
-        HandlerRef(h) => h.win,
-    };
+    let gamedir = &handler_gamedir(h)?;
+    let win = h.win;
 
     let mut cmd = String::new();
     // Command: "gamescope [settings] -- bwrap [binds] [runtime] [exec] [args] & ..."
@@ -87,90 +154,74 @@ pub fn launch_cmd(
 
     if cfg.force_sdl && !win {
         let mut path_sdl = "/ubuntu12_32/steam-runtime/usr/lib/x86_64-linux-gnu/libSDL2-2.0.so.0";
-        if let HandlerRef(h) = game {
-            if h.is32bit {
-                path_sdl = "/ubuntu12_32/steam-runtime/usr/lib/i386-linux-gnu/libSDL2-2.0.so.0";
-            }
-        };
+        if h.is32bit {
+            path_sdl = "/ubuntu12_32/steam-runtime/usr/lib/i386-linux-gnu/libSDL2-2.0.so.0";
+        }
         cmd.push_str(&format!("SDL_DYNAMIC_API=\"{steam}/{path_sdl}\" "));
     }
     if win {
-        let protonpath = match cfg.proton_version.is_empty() {
-            true => "GE-Proton",
-            false => cfg.proton_version.as_str(),
-        };
-        cmd.push_str(&format!("PROTON_VERB=run WINEPREFIX={party}/pfx "));
-        cmd.push_str(&format!("PROTONPATH={protonpath} "));
-
-        if let HandlerRef(h) = game {
-            if !h.dll_overrides.is_empty() {
-                cmd.push_str("WINEDLLOVERRIDES=\"");
-                for dll in &h.dll_overrides {
-                    cmd.push_str(&format!("{dll},"));
-                }
-                cmd.push_str("=n,b\" ");
-            }
-            if h.coldclient {
-                cmd.push_str("PROTON_DISABLE_LSTEAMCLIENT=1 ");
+        let protonpath = resolve_proton_path(&cfg.proton_version)?;
+        cmd.push_str(&format!(
+            "PROTON_VERB=run WINEPREFIX=\"{}\" ",
+            h.path_prefix(cfg).display()
+        ));
+        cmd.push_str(&format!("PROTONPATH=\"{protonpath}\" "));
+
+        if !h.dll_overrides.is_empty() {
+            cmd.push_str("WINEDLLOVERRIDES=\"");
+            for dll in &h.dll_overrides {
+                cmd.push_str(&format!("{dll},"));
             }
+            cmd.push_str("=n,b\" ");
         }
+        if h.coldclient {
+            cmd.push_str("PROTON_DISABLE_LSTEAMCLIENT=1 ");
+        }
+    }
+    for var in &overrides.env {
+        let (key, value) = var
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid environment variable: {var}"))?;
+        check_env_name(key)?;
+        cmd.push_str(&format!("{key}={} ", shell_quote(value)));
     }
     cmd.push_str("; ");
 
     let runtime = match win {
         true => &format!("{res}/umu-run"),
-        false => {
-            if let HandlerRef(h) = game {
-                match h.runtime.as_str() {
-                    "scout" => &format!("\"{steam}/ubuntu12_32/steam-runtime/run.sh\""),
-                    "soldier" => &format!(
-                        "\"{steam}/steamapps/common/SteamLinuxRuntime_soldier/_v2-entry-point\""
-                    ),
-                    _ => "",
-                }
-            } else {
-                ""
+        false => match h.runtime.as_str() {
+            "scout" => &format!("\"{steam}/ubuntu12_32/steam-runtime/run.sh\""),
+            "soldier" => {
+                &format!("\"{steam}/steamapps/common/SteamLinuxRuntime_soldier/_v2-entry-point\"")
             }
-        }
-    };
-
-    let exec = match game {
-        Executable { path, .. } => &path.to_string_lossy(),
-        HandlerRef(h) => h.exec.as_str(),
+            _ => "",
+        },
     };
 
-    if !PathBuf::from(gamedir).join(exec).exists() {
-        return Err(format!("Executable not found: {gamedir}/{exec}").into());
-    }
-
-    if let HandlerRef(h) = game {
-        if h.runtime == "scout" && !PATH_STEAM.join("ubuntu12_32/steam-runtime/run.sh").exists() {
-            return Err("Steam Scout Runtime not found".into());
-        } else if h.runtime == "soldier"
-            && !PATH_STEAM
-                .join("steamapps/common/SteamLinuxRuntime_soldier")
-                .exists()
-        {
-            return Err("Steam Soldier Runtime not found".into());
-        }
+    if h.runtime == "scout" && !PATH_STEAM.join("ubuntu12_32/steam-runtime/run.sh").exists() {
+        return Err("Steam Scout Runtime not found".into());
+    } else if h.runtime == "soldier"
+        && !PATH_STEAM
+            .join("steamapps/common/SteamLinuxRuntime_soldier")
+            .exists()
+    {
+        return Err("Steam Soldier Runtime not found".into());
     }
 
-    let (screen_width, screen_height) = get_screen_resolution();
-    let scale_factor = cfg.render_scale as f32 / 100.0;
-    let width = (screen_width as f32 * scale_factor) as u32;
-    let height = (screen_height as f32 * scale_factor) as u32;
-
     cmd.push_str(&format!("cd \"{gamedir}\"; "));
 
-    for (i, instance) in instances.iter().enumerate() {
-        let path_prof = &format!("{party}/profiles/{}", instance.profname.as_str());
-        let path_save = match game {
-            Executable { .. } => "",
-            HandlerRef(h) => &format!("{path_prof}/saves/{}", h.uid.as_str()),
-        };
+    let launches = instance_launches(h, input_devices, instances, cfg, gamedir);
+    for (i, (instance, launch)) in instances.iter().zip(&launches).enumerate() {
+        let path_prof = &launch.path_prof;
+        let path_save = &launch.path_save;
+        let (gsc_width, gsc_height) = (launch.width, launch.height);
+        let vars = &launch.vars;
 
-        let (gsc_width, gsc_height) =
-            get_instance_resolution(instances.len(), i, width, height, cfg.vertical_two_player);
+        let exec = expand_template_path(&h.exec, vars)?;
+
+        if !PathBuf::from(gamedir).join(&exec).exists() {
+            return Err(format!("Executable not found: {gamedir}/{exec}").into());
+        }
 
         if gsc_height < 600 && gsc_lowres_warn {
             msg(
@@ -244,53 +295,55 @@ pub fn launch_cmd(
             }
         }
 
-        if let HandlerRef(h) = game {
-            let path_goldberg = h.path_goldberg.as_str();
-            if !path_goldberg.is_empty() {
+        let path_goldberg = expand_template_path(&h.path_goldberg, vars)?;
+        if !path_goldberg.is_empty() {
+            binds.push_str(&format!(
+                "--bind \"{path_prof}/steam\" \"{gamedir}/{path_goldberg}/goldbergsave\" "
+            ));
+        }
+        if h.win {
+            let path_windata = format!("{}/drive_c/users/steamuser/", h.path_prefix(cfg).display());
+            if h.win_unique_appdata {
                 binds.push_str(&format!(
-                    "--bind \"{path_prof}/steam\" \"{gamedir}/{path_goldberg}/goldbergsave\" "
+                    "--bind \"{path_save}/_AppData\" \"{path_windata}/AppData\" "
                 ));
             }
-            if h.win {
-                let path_windata = format!("{party}/pfx/drive_c/users/steamuser/");
-                if h.win_unique_appdata {
-                    binds.push_str(&format!(
-                        "--bind \"{path_save}/_AppData\" \"{path_windata}/AppData\" "
-                    ));
-                }
-                if h.win_unique_documents {
-                    binds.push_str(&format!(
-                        "--bind \"{path_save}/_Documents\" \"{path_windata}/Documents\" "
-                    ));
-                }
-            } else {
-                if h.linux_unique_localshare {
-                    binds.push_str(&format!("--bind \"{path_save}/_share\" \"{localshare}\" "));
-                }
-                if h.linux_unique_config {
-                    binds.push_str(&format!(
-                        "--bind \"{path_save}/_config\" \"{home}/.config\" "
-                    ));
-                }
+            if h.win_unique_documents {
+                binds.push_str(&format!(
+                    "--bind \"{path_save}/_Documents\" \"{path_windata}/Documents\" "
+                ));
+            }
+        } else {
+            if h.linux_unique_localshare {
+                binds.push_str(&format!("--bind \"{path_save}/_share\" \"{localshare}\" "));
             }
-            for subdir in &h.game_unique_paths {
+            if h.linux_unique_config {
                 binds.push_str(&format!(
-                    "--bind \"{path_save}/{subdir}\" \"{gamedir}/{subdir}\" "
+                    "--bind \"{path_save}/_config\" \"{home}/.config\" "
                 ));
             }
+        }
+        for path in &h.game_unique_paths {
+            let subdir = expand_template_path(path, vars)?;
+            binds.push_str(&format!(
+                "--bind \"{path_save}/{subdir}\" \"{gamedir}/{subdir}\" "
+            ));
+        }
+
+        // Handler env is set per instance, so values can use instance variables such as $PLAYER_INDEX
+        for (key, value) in &h.env {
+            check_env_name(key)?;
+            binds.push_str(&format!(
+                "--setenv {key} {} ",
+                shell_quote(&expand_template(value, vars)?)
+            ));
+        }
 
-            args = h
-                .args
-                .iter()
-                .map(|arg| match arg.as_str() {
-                    "$GAMEDIR" => format!(" \"{gamedir}\""),
-                    "$PROFILE" => format!(" \"{}\"", instance.profname.as_str()),
-                    "$WIDTH" => format!(" {gsc_width}"),
-                    "$HEIGHT" => format!(" {gsc_height}"),
-                    "$WIDTHXHEIGHT" => format!(" \"{gsc_width}x{gsc_height}\""),
-                    _ => format!(" {arg}"),
-                })
-                .collect::<String>();
+        for arg in h.args.iter().flat_map(|arg| split_legacy_arg(arg)) {
+            args.push_str(&format!(" {}", shell_quote(&expand_template(arg, vars)?)));
+        }
+        for arg in &overrides.args {
+            args.push_str(&format!(" {}", shell_quote(&expand_template(arg, vars)?)));
         }
 
         cmd.push_str(&format!("{binds} {runtime} \"{gamedir}/{exec}\"{args} "));
@@ -307,3 +360,155 @@ pub fn launch_cmd(
 
     Ok(cmd)
 }
+
+// The folder the game runs from: the symlinked copy if the handler uses one, otherwise the install folder
+fn handler_gamedir(h: &Handler) -> Result<String, Box<dyn std::error::Error>> {
+    match h.symlink_dir {
+        true => Ok(PATH_PARTY
+            .join("gamesyms")
+            .join(&h.uid)
+            .to_string_lossy()
+            .to_string()),
+        false => get_rootpath_handler(h),
+    }
+}
+
+// Folders, resolution and template variables of one instance
+struct InstanceLaunch {
+    path_prof: String,
+    path_save: String,
+    width: u32,
+    height: u32,
+    vars: TemplateVars,
+}
+
+fn instance_launches(
+    h: &Handler,
+    input_devices: &[DeviceInfo],
+    instances: &[Instance],
+    cfg: &PartyConfig,
+    gamedir: &str,
+) -> Vec<InstanceLaunch> {
+    let (screen_width, screen_height) = get_screen_resolution();
+    let scale_factor = cfg.render_scale as f32 / 100.0;
+    let width = (screen_width as f32 * scale_factor) as u32;
+    let height = (screen_height as f32 * scale_factor) as u32;
+
+    let mut out = Vec::new();
+    for (i, instance) in instances.iter().enumerate() {
+        let path_prof = format!("{}/profiles/{}", PATH_PARTY.display(), instance.profname);
+        let path_save = format!("{path_prof}/saves/{}", h.uid);
+        let (width, height) =
+            get_instance_resolution(instances.len(), i, width, height, cfg.vertical_two_player);
+
+        let mut vars = instance_vars(instances, i, gamedir, &path_prof, &path_save, width, height);
+        vars.insert(
+            "CONTROLLER_INDEX",
+            controller_index(instance, input_devices).to_string(),
+        );
+        out.push(InstanceLaunch {
+            path_prof,
+            path_save,
+            width,
+            height,
+            vars,
+        });
+    }
+    out
+}
+
+// Creates the templated game_unique_paths folders and renders the handler's templates into each
+// instance's game save. Done before launch_cmd, which only builds the command.
+fn prepare_instance_saves(
+    h: &Handler,
+    input_devices: &[DeviceInfo],
+    instances: &[Instance],
+    cfg: &PartyConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let gamedir = handler_gamedir(h)?;
+    for launch in instance_launches(h, input_devices, instances, cfg, &gamedir) {
+        for path in &h.game_unique_paths {
+            let subdir = expand_template_path(path, &launch.vars)?;
+            // Templated paths aren't created with the game save, see create_gamesave
+            if subdir != *path && !subdir.contains('.') {
+                std::fs::create_dir_all(format!("{}/{subdir}", launch.path_save))?;
+            }
+        }
+        // Per-instance config files go into the game save, like copy_to_profilesave
+        render_templates(
+            &h.asset("templates"),
+            &PathBuf::from(&launch.path_save),
+            &launch.vars,
+        )?;
+    }
+    Ok(())
+}
+
+// Handlers written before args were quoted put several arguments in one element, e.g.
+// "-windowed -nosound". Elements without template variables are still split on whitespace.
+pub fn split_legacy_arg(arg: &str) -> Vec<&str> {
+    match arg.contains('$') {
+        true => vec![arg],
+        false => arg.split_whitespace().collect(),
+    }
+}
+
+// Variables for handler templates, see util/template.rs
+fn instance_vars(
+    instances: &[Instance],
+    i: usize,
+    gamedir: &str,
+    path_prof: &str,
+    path_save: &str,
+    width: u32,
+    height: u32,
+) -> TemplateVars {
+    let profname = instances[i].profname.as_str();
+    TemplateVars::from([
+        ("GAMEDIR", gamedir.to_string()),
+        ("PROFILE", profname.to_string()),
+        ("PROFILE_DIR", path_prof.to_string()),
+        ("SAVEDIR", path_save.to_string()),
+        ("WIDTH", width.to_string()),
+        ("HEIGHT", height.to_string()),
+        ("WIDTHXHEIGHT", format!("{width}x{height}")),
+        ("PLAYER_INDEX", i.to_string()),
+        ("PLAYER_COUNT", instances.len().to_string()),
+        (
+            "STEAMID",
+            profile_setting(profname, "account_steamid").unwrap_or_default(),
+        ),
+        (
+            "LANG",
+            profile_setting(profname, "language").unwrap_or("english".to_string()),
+        ),
+    ])
+}
+
+// Position of the instance's first gamepad among the enabled gamepads, or -1 if it has none
+fn controller_index(instance: &Instance, input_devices: &[DeviceInfo]) -> i32 {
+    let gamepads: Vec<usize> = input_devices
+        .iter()
+        .enumerate()
+        .filter(|(_, dev)| dev.enabled && dev.device_type == DeviceType::Gamepad)
+        .map(|(d, _)| d)
+        .collect();
+    instance
+        .devices
+        .iter()
+        .find_map(|d| gamepads.iter().position(|g| g == d))
+        .map(|pos| pos as i32)
+        .unwrap_or(-1)
+}
+
+fn check_env_name(key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid environment variable name: {key}").into());
+    }
+    Ok(())
+}
+
+// Wraps a value in single quotes so the shell passes it through untouched
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
@@ -0,0 +1,172 @@
+use crate::game::Game;
+use crate::paths::*;
+use crate::util::{lock_file, with_suffix, write_atomic};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// What PartyDeck remembers about a game between sessions
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GameStats {
+    pub favorite: bool,
+    // Unix time in seconds, 0 if never played
+    pub last_played: u64,
+    pub play_count: u32,
+}
+
+// Favorites and play counts by game uid, stored in library.json
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Library {
+    pub games: BTreeMap<String, GameStats>,
+}
+
+impl Library {
+    pub fn stats(&self, uid: &str) -> GameStats {
+        self.games.get(uid).cloned().unwrap_or_default()
+    }
+
+    pub fn is_favorite(&self, uid: &str) -> bool {
+        self.games.get(uid).is_some_and(|stats| stats.favorite)
+    }
+}
+
+fn path_library() -> PathBuf {
+    PATH_PARTY.join("library.json")
+}
+
+// Reads library.json, falling back to the backup if it's unreadable
+pub fn load_library() -> Library {
+    let _lock = lock_file(&path_library(), false);
+    read_library()
+}
+
+fn read_library() -> Library {
+    let path = path_library();
+    for file in [path.clone(), with_suffix(&path, ".bak")] {
+        if !file.exists() {
+            continue;
+        }
+        match read_library_file(&file) {
+            Ok(library) => return library,
+            Err(err) => println!("Couldn't read {}: {err}", file.display()),
+        }
+    }
+    Library::default()
+}
+
+fn read_library_file(file: &Path) -> Result<Library, Box<dyn Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(file)?))?)
+}
+
+// Loads the library, applies f and saves the result, all under one lock, like PathsStore::update
+fn update_library(f: impl FnOnce(&mut Library)) -> Result<Library, Box<dyn Error>> {
+    let path = path_library();
+    let _lock = lock_file(&path, true)?;
+    let mut library = read_library();
+    f(&mut library);
+    // A broken library.json mustn't replace a good backup
+    let backup = read_library_file(&path).is_ok();
+    write_atomic(
+        &path,
+        serde_json::to_string_pretty(&library)?.as_bytes(),
+        backup,
+    )?;
+    Ok(library)
+}
+
+pub fn set_favorite(uid: &str, favorite: bool) -> Result<Library, Box<dyn Error>> {
+    update_library(|library| {
+        library.games.entry(uid.to_string()).or_default().favorite = favorite;
+    })
+}
+
+// Moves a game's stats to a new uid, unless the new uid already has some
+pub fn rename_library_game(old_uid: &str, new_uid: &str) -> Result<(), Box<dyn Error>> {
+    update_library(|library| {
+        if let Some(stats) = library.games.remove(old_uid) {
+            library.games.entry(new_uid.to_string()).or_insert(stats);
+        }
+    })?;
+    Ok(())
+}
+
+pub fn record_play(uid: &str) -> Result<(), Box<dyn Error>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    update_library(|library| {
+        let stats = library.games.entry(uid.to_string()).or_default();
+        stats.last_played = now;
+        stats.play_count += 1;
+    })?;
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum PlatformFilter {
+    #[default]
+    All,
+    Native,
+    Proton,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum KindFilter {
+    #[default]
+    All,
+    Handlers,
+    Executables,
+}
+
+// Search and filters of the games list
+#[derive(Clone, Default)]
+pub struct GameFilter {
+    pub search: String,
+    pub platform: PlatformFilter,
+    pub kind: KindFilter,
+    // Only games that support at least this many players; games that don't say are always shown
+    pub min_players: u32,
+}
+
+impl GameFilter {
+    pub fn matches(&self, game: &Game) -> bool {
+        let search = self.search.trim().to_lowercase();
+        if !search.is_empty()
+            && !game.name().to_lowercase().contains(&search)
+            && !game.uid().to_lowercase().contains(&search)
+        {
+            return false;
+        }
+
+        let win = match game {
+            Game::Executable(entry) => entry.is_win(),
+            Game::HandlerRef(h) => h.win,
+        };
+        let platform = match self.platform {
+            PlatformFilter::All => true,
+            PlatformFilter::Native => !win,
+            PlatformFilter::Proton => win,
+        };
+        let kind = matches!(
+            (self.kind, game),
+            (KindFilter::All, _)
+                | (KindFilter::Handlers, Game::HandlerRef(_))
+                | (KindFilter::Executables, Game::Executable(_))
+        );
+        let players = match game {
+            Game::HandlerRef(h) if h.max_players > 0 => h.max_players >= self.min_players,
+            _ => true,
+        };
+
+        platform && kind && players
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.platform != PlatformFilter::All || self.kind != KindFilter::All || self.min_players > 0
+    }
+}
@@ -0,0 +1,321 @@
+use crate::handler::{Handler, extract_handler_archive, read_handler_json};
+use crate::launch::split_legacy_arg;
+use crate::paths::*;
+use crate::util::*;
+
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+// Lints a handler folder or a .pdh archive
+pub fn lint_handler_path(path: &PathBuf) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    if path.is_dir() {
+        return Ok(lint_handler_dir(path));
+    }
+    if path.extension().unwrap_or_default() != "pdh" {
+        return Err("Expected a handler folder or a .pdh file".into());
+    }
+
+    let dir_tmp = PATH_PARTY
+        .join("tmp")
+        .join(format!("lint-{}", fastrand::u32(..)));
+    std::fs::create_dir_all(&dir_tmp)?;
+
+    let result = extract_handler_archive(path, &dir_tmp).map(|_| lint_handler_dir(&dir_tmp));
+    let _ = std::fs::remove_dir_all(&dir_tmp);
+
+    result
+}
+
+pub fn lint_handler_dir(dir: &PathBuf) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let mut error = |message: String| {
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            message,
+        })
+    };
+
+    let json_path = dir.join("handler.json");
+    match File::open(&json_path) {
+        Ok(file) => {
+            if let Err(err) = serde_json::from_reader::<_, Value>(BufReader::new(file)) {
+                error(format!("handler.json is not valid JSON: {err}"));
+                return out;
+            }
+        }
+        Err(_) => {
+            error(format!("handler.json not found in {}", dir.display()));
+            return out;
+        }
+    }
+
+    match read_handler_json(&json_path, &mut Vec::new()) {
+        Ok(merged) => lint_handler_json(&merged, dir),
+        Err(err) => {
+            error(format!("Couldn't load handler: {err}"));
+            out
+        }
+    }
+}
+
+// Lints handler JSON that may not be saved yet, such as the handler editor's draft.
+// path_handler is the folder its scripts, templates and icon are looked up in.
+pub fn lint_handler_json(json: &Value, path_handler: &PathBuf) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+
+    match Handler::from_json(json, path_handler) {
+        Ok(h) => lint_handler(&h, json, &mut out),
+        Err(err) => out.push(Diagnostic {
+            severity: Severity::Error,
+            message: format!("Couldn't load handler: {err}"),
+        }),
+    }
+
+    out
+}
+
+fn lint_handler(h: &Handler, json: &Value, out: &mut Vec<Diagnostic>) {
+    let mut error = |message: String| {
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            message,
+        })
+    };
+
+    // The same checks that stop a handler from being installed
+    for message in h.validation_errors() {
+        error(message);
+    }
+
+    // Templates are checked with placeholder values, so only unknown variables and bad syntax show up here
+    let vars = placeholder_vars();
+    for (key, template) in std::iter::once(("game.exec", &h.exec))
+        .chain(std::iter::once(("steam.api_path", &h.path_goldberg)))
+        .chain(h.args.iter().map(|arg| ("game.args", arg)))
+        .chain(
+            h.game_unique_paths
+                .iter()
+                .map(|path| ("profiles.game_paths", path)),
+        )
+    {
+        if let Err(err) = expand_template(template, &vars) {
+            error(format!("{key}: {err}"));
+        }
+    }
+    let path_templates = h.asset("templates");
+    for entry in walkdir::WalkDir::new(&path_templates)
+        .min_depth(1)
+        .into_iter()
+        .flatten()
+    {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().unwrap_or_default() != "tmpl" {
+            continue;
+        }
+        let rel_path = path.strip_prefix(&path_templates).unwrap_or(path).display();
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                if let Err(err) = expand_template(&contents, &vars) {
+                    error(format!("templates/{rel_path}: {err}"));
+                }
+            }
+            Err(err) => error(format!("templates/{rel_path} can't be read as text: {err}")),
+        }
+    }
+    for (key, script) in h.hooks() {
+        let path = h.asset(script);
+        if !path.is_file() {
+            error(format!(
+                "{key} script \"{script}\" not found in the handler folder"
+            ));
+        } else if std::fs::metadata(&path).is_ok_and(|m| m.permissions().mode() & 0o111 == 0) {
+            error(format!("{key} script \"{script}\" is not executable"));
+        }
+    }
+    if !json["variants"].is_null() && !json["variants"].is_array() {
+        error("variants must be a list of objects".to_string());
+    }
+    for variant in json["variants"].as_array().into_iter().flatten() {
+        if !variant["handler.uid"].is_null() || !variant["variants"].is_null() {
+            error("variants can't set handler.uid or variants".to_string());
+        }
+        if variant["game.exec"].as_str().unwrap_or_default().is_empty() {
+            error("Every variant needs its own game.exec to be selected by".to_string());
+        }
+    }
+    if json["game.max_players"].is_string() {
+        error("game.max_players must be a number".to_string());
+    }
+    if !json["game.env"].is_null() && !json["game.env"].is_object() {
+        error("game.env must be an object of variable names to values".to_string());
+    }
+    for (key, value) in &h.env {
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            error(format!("game.env: invalid variable name \"{key}\""));
+        }
+        if let Err(err) = expand_template(value, &vars) {
+            error(format!("game.env {key}: {err}"));
+        }
+    }
+
+    let mut warning = |message: String| {
+        out.push(Diagnostic {
+            severity: Severity::Warning,
+            message,
+        })
+    };
+
+    if h.win && !h.runtime.is_empty() {
+        warning("game.runtime is ignored for Windows games".to_string());
+    }
+    if !h.asset("icon.png").exists() {
+        warning("icon.png not found in the handler folder".to_string());
+    }
+    for arg in &h.args {
+        if split_legacy_arg(arg).len() > 1 {
+            warning(format!(
+                "game.args element \"{arg}\" is split on spaces into several arguments; put each argument in its own element"
+            ));
+        }
+    }
+
+    // Values that sanitize_path had to change won't point where the author intended
+    for key in ["game.exec", "steam.api_path"] {
+        if let Some(raw) = json[key].as_str() {
+            lint_sanitized(key, raw, &mut warning);
+        }
+    }
+    for key in [
+        "game.copy_instead_paths",
+        "game.remove_paths",
+        "profiles.game_paths",
+    ] {
+        for raw in json[key].as_array().into_iter().flatten() {
+            if let Some(raw) = raw.as_str() {
+                lint_sanitized(key, raw, &mut warning);
+            }
+        }
+    }
+
+    // Linting mustn't save a path it happens to find, so paths.json is only read
+    let path_root = match peek_rootpath_handler(h) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            warning(
+                "Couldn't locate the game's install folder, skipping checks against game files"
+                    .to_string(),
+            );
+            return;
+        }
+    };
+
+    // Check the edition that's installed, as launch_game does. select_variant only falls back to the
+    // handler itself when no variant's game.exec exists either.
+    let has_variants = !h.variants.is_empty();
+    let h = &h.select_variant(&path_root);
+
+    if !h.exec.is_empty() && !h.exec.contains('$') && !path_root.join(&h.exec).exists() {
+        let variants = match has_variants {
+            true => ", nor is any variant's game.exec",
+            false => "",
+        };
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            message: format!(
+                "game.exec \"{}\" not found in {}{variants}",
+                h.exec,
+                path_root.display()
+            ),
+        });
+    }
+
+    if !h.path_goldberg.is_empty() && !h.coldclient {
+        lint_steam_api(h, &path_root, out);
+    }
+
+    for path in &h.game_unique_paths {
+        if !path.is_empty() && !path.contains('$') && !path_root.join(path).exists() {
+            out.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!(
+                    "profiles.game_paths entry \"{path}\" not found in {}",
+                    path_root.display()
+                ),
+            });
+        }
+    }
+}
+
+fn lint_sanitized(key: &str, raw: &str, warning: &mut impl FnMut(String)) {
+    let sanitized = raw.to_string().sanitize_path();
+    if sanitized != raw {
+        warning(format!(
+            "{key} value \"{raw}\" was changed to \"{sanitized}\" by path sanitization"
+        ));
+    }
+}
+
+fn lint_steam_api(h: &Handler, path_root: &PathBuf, out: &mut Vec<Diagnostic>) {
+    let path_steamdll = match h.path_goldberg.as_str() {
+        "." => path_root.to_owned(),
+        _ => path_root.join(&h.path_goldberg),
+    };
+    if !path_steamdll.is_dir() {
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            message: format!(
+                "steam.api_path directory \"{}\" not found",
+                path_steamdll.display()
+            ),
+        });
+        return;
+    }
+
+    let (expected, other) = match (h.win, h.is32bit) {
+        (true, true) => ("steam_api.dll", Some("steam_api64.dll")),
+        (true, false) => ("steam_api64.dll", Some("steam_api.dll")),
+        (false, _) => ("libsteam_api.so", None),
+    };
+    if path_steamdll.join(expected).exists() {
+        return;
+    }
+
+    let message = match other {
+        Some(other) if path_steamdll.join(other).exists() => {
+            format!("steam.api_path contains {other} instead of {expected}; check game.32bit")
+        }
+        _ => format!(
+            "{expected} not found in steam.api_path \"{}\"",
+            path_steamdll.display()
+        ),
+    };
+    out.push(Diagnostic {
+        severity: Severity::Error,
+        message,
+    });
+}
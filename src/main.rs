@@ -1,9 +1,20 @@
 mod app;
+mod catalogue;
+mod cli;
+mod desktop_entries;
+mod executable;
 mod game;
 mod handler;
+mod history;
+mod hooks;
 mod input;
 mod launch;
+mod library;
+mod lint;
 mod paths;
+mod presets;
+mod signing;
+mod steam_shortcuts;
 mod util;
 
 use crate::app::*;
@@ -18,6 +29,12 @@ fn main() -> eframe::Result {
     std::fs::create_dir_all(PATH_PARTY.join("profiles"))
         .expect("Failed to create profiles directory");
 
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = cli::run_cli(&args) {
+        std::process::exit(code);
+    }
+    let start_game = cli::start_game(&args);
+
     remove_guest_profiles().unwrap();
 
     if PATH_PARTY.join("tmp").exists() {
@@ -26,7 +43,7 @@ fn main() -> eframe::Result {
 
     println!("\n[PARTYDECK] started\n");
 
-    let fullscreen = std::env::args().any(|arg| arg == "--fullscreen");
+    let fullscreen = args.iter().any(|arg| arg == "--fullscreen");
 
     let (_, scrheight) = get_screen_resolution();
 
@@ -53,7 +70,11 @@ fn main() -> eframe::Result {
             // This gives us image support:
             egui_extras::install_image_loaders(&cc.egui_ctx);
             cc.egui_ctx.set_zoom_factor(scale);
-            Ok(Box::<PartyApp>::default())
+            let mut app = PartyApp::default();
+            if let Some(uid) = start_game {
+                app.open_game(&uid);
+            }
+            Ok(Box::new(app))
         }),
     )
 }
@@ -0,0 +1,211 @@
+use crate::app::PartyConfig;
+use crate::game::{Game, scan_all_games};
+use crate::history::device_type_name;
+use crate::input::{DeviceInfo, Instance, scan_input_devices};
+use crate::launch::launch_game;
+use crate::paths::*;
+use crate::util::{guest_names, lock_file, remove_guest_profiles, with_suffix, write_atomic};
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+// A saved set of players for a game, so a session can be started without going through the
+// Instances page, e.g. from a Steam shortcut
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    // Uid of the game
+    pub game: String,
+    // Profile of each player, empty for a guest
+    pub profiles: Vec<String>,
+    // Types of the devices of each player, e.g. ["Gamepad"] or ["Keyboard", "Mouse"]
+    pub devices: Vec<Vec<String>>,
+}
+
+impl Preset {
+    // profiles holds the profile chosen for each instance, empty for guests
+    pub fn from_instances(
+        name: &str,
+        game: &str,
+        instances: &[Instance],
+        profiles: &[String],
+        input_devices: &[DeviceInfo],
+    ) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            game: game.to_string(),
+            profiles: profiles.to_vec(),
+            devices: instances
+                .iter()
+                .map(|i| {
+                    i.devices
+                        .iter()
+                        .map(|&d| device_type_name(input_devices[d].device_type).to_string())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    // Gives each player the first connected devices of the types they used when the preset was saved
+    pub fn instances(&self, input_devices: &[DeviceInfo]) -> Result<Vec<Instance>, Box<dyn Error>> {
+        let mut used = vec![false; input_devices.len()];
+        let mut guests = guest_names(self.profiles.len());
+        let mut out = Vec::new();
+
+        for (player, device_types) in self.devices.iter().enumerate() {
+            let mut devices = Vec::new();
+            for device_type in device_types {
+                let Some(d) = (0..input_devices.len()).find(|&d| {
+                    !used[d]
+                        && input_devices[d].enabled
+                        && device_type_name(input_devices[d].device_type) == device_type
+                }) else {
+                    return Err(format!(
+                        "Not enough devices connected for preset \"{}\": player {} needs a {device_type}",
+                        self.name,
+                        player + 1
+                    )
+                    .into());
+                };
+                used[d] = true;
+                devices.push(d);
+            }
+
+            let profname = match self.profiles.get(player) {
+                Some(profile) if !profile.is_empty() => profile.clone(),
+                _ => guests.pop().unwrap_or_default(),
+            };
+            out.push(Instance {
+                devices,
+                profname,
+                profselection: 0,
+            });
+        }
+
+        if out.is_empty() {
+            return Err(format!("Preset \"{}\" has no players", self.name).into());
+        }
+        Ok(out)
+    }
+}
+
+// Preset names end up in command lines and file names, so only simple ones are allowed
+pub fn validate_preset_name(name: &str) -> Result<(), Box<dyn Error>> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Preset name can't be empty".into());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        return Err("Preset names can only contain letters, numbers, spaces, - and _".into());
+    }
+    Ok(())
+}
+
+fn path_presets() -> PathBuf {
+    PATH_PARTY.join("presets.json")
+}
+
+// Every saved preset, sorted by name. Falls back to the backup if presets.json is unreadable.
+pub fn load_presets() -> Vec<Preset> {
+    let _lock = lock_file(&path_presets(), false);
+    read_presets()
+}
+
+fn read_presets() -> Vec<Preset> {
+    let path = path_presets();
+    for file in [path.clone(), with_suffix(&path, ".bak")] {
+        if !file.exists() {
+            continue;
+        }
+        match read_presets_file(&file) {
+            Ok(presets) => return presets,
+            Err(err) => println!("Couldn't read {}: {err}", file.display()),
+        }
+    }
+    Vec::new()
+}
+
+fn read_presets_file(file: &Path) -> Result<Vec<Preset>, Box<dyn Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(file)?))?)
+}
+
+// Loads the presets, applies f and saves the result, all under one lock, like PathsStore::update
+fn update_presets(f: impl FnOnce(&mut Vec<Preset>)) -> Result<Vec<Preset>, Box<dyn Error>> {
+    let path = path_presets();
+    let _lock = lock_file(&path, true)?;
+    let mut presets = read_presets();
+    f(&mut presets);
+    // A broken presets.json mustn't replace a good backup
+    let backup = read_presets_file(&path).is_ok();
+    write_atomic(
+        &path,
+        serde_json::to_string_pretty(&presets)?.as_bytes(),
+        backup,
+    )?;
+    Ok(presets)
+}
+
+// Adds the preset, replacing the game's preset with the same name. Different games can have
+// presets with the same name.
+pub fn save_preset(preset: &Preset) -> Result<Vec<Preset>, Box<dyn Error>> {
+    validate_preset_name(&preset.name)?;
+    update_presets(|presets| {
+        presets.retain(|p| p.game != preset.game || p.name != preset.name);
+        presets.push(preset.clone());
+        presets.sort_by_key(|p| p.name.to_lowercase());
+    })
+}
+
+pub fn remove_preset(game: &str, name: &str) -> Result<Vec<Preset>, Box<dyn Error>> {
+    update_presets(|presets| presets.retain(|p| p.game != game || p.name != name))
+}
+
+// Finds the game's preset. Without a game, the name has to be unique across games, which keeps
+// shortcuts made before presets were per game working.
+pub fn find_preset(game: Option<&str>, name: &str) -> Result<Preset, Box<dyn Error>> {
+    let mut matches: Vec<Preset> = load_presets()
+        .into_iter()
+        .filter(|p| p.name == name && game.is_none_or(|game| p.game == game))
+        .collect();
+    match (matches.len(), game) {
+        (1, _) => Ok(matches.remove(0)),
+        (0, Some(game)) => Err(format!("{game} has no preset named \"{name}\"").into()),
+        (0, None) => Err(format!("No preset named \"{name}\"").into()),
+        _ => Err(format!(
+            "Several games have a preset named \"{name}\", use partydeck launch <game uid> --preset \"{name}\""
+        )
+        .into()),
+    }
+}
+
+// Starts the preset's game with the devices connected right now, and waits for the session to end
+pub fn launch_preset(preset: &Preset, cfg: &PartyConfig) -> Result<(), Box<dyn Error>> {
+    let game: Game = scan_all_games()
+        .into_iter()
+        .find(|g| g.uid() == preset.game)
+        .ok_or_else(|| {
+            format!(
+                "Game \"{}\" of preset \"{}\" isn't installed",
+                preset.game, preset.name
+            )
+        })?;
+
+    let input_devices: Vec<DeviceInfo> = scan_input_devices(&cfg.pad_filter_type)
+        .iter()
+        .map(|d| d.info())
+        .collect();
+    let instances = preset.instances(&input_devices)?;
+
+    // Progress is already printed to stdout, which is all there is without the GUI
+    let result = launch_game(&game, &input_devices, &instances, cfg, &|_| {});
+    remove_guest_profiles()?;
+    result
+}
@@ -0,0 +1,168 @@
+use crate::paths::*;
+use crate::util::{lock_file, write_atomic};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+// Name of the signature file inside a handler archive. It isn't part of the signed manifest.
+pub const SIGNATURE_FILE: &str = "signature.json";
+
+#[derive(Serialize, Deserialize)]
+struct HandlerSignature {
+    key: String,
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrustedKey {
+    pub name: String,
+    pub key: String,
+}
+
+pub enum SignatureStatus {
+    Unsigned,
+    Trusted(String),
+    UnknownSigner(String),
+    Invalid,
+}
+
+// Lists every file in the handler folder with its permissions and sha256, sorted by path, one
+// "path\tmode\tsha256" per line. This is what gets signed, so the signature covers the contents of
+// every file in the handler and which of them are executable, as both are kept on install.
+// Symlinks and the paths in skip are left out, as they are when exporting.
+pub fn handler_manifest(dir: &PathBuf, skip: &[PathBuf]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for entry in walkdir::WalkDir::new(dir).min_depth(1) {
+        let entry = entry?;
+        if entry.file_type().is_dir()
+            || entry.file_type().is_symlink()
+            || skip.iter().any(|p| p == entry.path())
+        {
+            continue;
+        }
+        let rel_path = entry
+            .path()
+            .strip_prefix(dir)?
+            .to_string_lossy()
+            .to_string();
+        if rel_path == SIGNATURE_FILE {
+            continue;
+        }
+        // Installing only restores the permission bits, see extract_handler_archive
+        let mode = entry.metadata()?.permissions().mode() & 0o777;
+        let checksum = Sha256::digest(std::fs::read(entry.path())?);
+        lines.push(format!("{rel_path}\t{mode:o}\t{checksum:x}\n"));
+    }
+
+    lines.sort();
+    Ok(lines.concat().into_bytes())
+}
+
+// Returns the contents of signature.json for the handler folder
+pub fn sign_handler(
+    dir: &PathBuf,
+    key: &SigningKey,
+    skip: &[PathBuf],
+) -> Result<String, Box<dyn Error>> {
+    let signature = key.sign(&handler_manifest(dir, skip)?);
+    let json = HandlerSignature {
+        key: hex::encode(key.verifying_key().to_bytes()),
+        signature: hex::encode(signature.to_bytes()),
+    };
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+pub fn verify_handler(dir: &PathBuf) -> Result<SignatureStatus, Box<dyn Error>> {
+    let path_sig = dir.join(SIGNATURE_FILE);
+    if !path_sig.exists() {
+        return Ok(SignatureStatus::Unsigned);
+    }
+
+    let json: HandlerSignature = serde_json::from_str(&std::fs::read_to_string(path_sig)?)?;
+    let key_bytes: [u8; 32] = hex::decode(&json.key)?
+        .try_into()
+        .map_err(|_| "Invalid signing key length")?;
+    let sig_bytes: [u8; 64] = hex::decode(&json.signature)?
+        .try_into()
+        .map_err(|_| "Invalid signature length")?;
+
+    let key = VerifyingKey::from_bytes(&key_bytes)?;
+    let signature = Signature::from_bytes(&sig_bytes);
+    let manifest = handler_manifest(dir, &[])?;
+    if key.verify(&manifest, &signature).is_err() {
+        return Ok(SignatureStatus::Invalid);
+    }
+
+    let key_hex = hex::encode(key_bytes);
+    match load_trusted_keys().into_iter().find(|k| k.key == key_hex) {
+        Some(trusted) => Ok(SignatureStatus::Trusted(trusted.name)),
+        None => Ok(SignatureStatus::UnknownSigner(key_hex)),
+    }
+}
+
+pub fn load_trusted_keys() -> Vec<TrustedKey> {
+    std::fs::read_to_string(PATH_PARTY.join("trusted_keys.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn trust_key(name: &str, key: &str) -> Result<(), Box<dyn Error>> {
+    let key = key.trim().to_lowercase();
+    let key_bytes: [u8; 32] = hex::decode(&key)?
+        .try_into()
+        .map_err(|_| "Invalid key length")?;
+    VerifyingKey::from_bytes(&key_bytes)?;
+
+    let path = PATH_PARTY.join("trusted_keys.json");
+    let _lock = lock_file(&path, true)?;
+    let mut keys = load_trusted_keys();
+    keys.retain(|k| k.key != key);
+    keys.push(TrustedKey {
+        name: name.to_string(),
+        key,
+    });
+    // An interrupted write mustn't lose every trusted key
+    write_atomic(
+        &path,
+        serde_json::to_string_pretty(&keys)?.as_bytes(),
+        path.exists(),
+    )
+}
+
+// The key used to sign exported handlers, if one has been generated
+pub fn load_signing_key() -> Option<SigningKey> {
+    let secret = std::fs::read_to_string(PATH_PARTY.join("signing_key")).ok()?;
+    let bytes: [u8; 32] = hex::decode(secret.trim()).ok()?.try_into().ok()?;
+    Some(SigningKey::from_bytes(&bytes))
+}
+
+// Creates a signing key for exported handlers and returns its public key
+pub fn generate_signing_key() -> Result<String, Box<dyn Error>> {
+    let path = PATH_PARTY.join("signing_key");
+    if path.exists() {
+        return Err(format!("A signing key already exists at {}", path.display()).into());
+    }
+
+    let mut secret = [0u8; 32];
+    rand::rng().fill_bytes(&mut secret);
+    let key = SigningKey::from_bytes(&secret);
+
+    // Only readable by the user from the moment it's created; create_new also refuses to replace a key
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
+    file.write_all(hex::encode(secret).as_bytes())?;
+
+    Ok(hex::encode(key.verifying_key().to_bytes()))
+}
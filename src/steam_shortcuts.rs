@@ -0,0 +1,306 @@
+use crate::game::Game;
+use crate::paths::*;
+use crate::presets::Preset;
+use crate::util::write_atomic;
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+// A value in Steam's binary VDF format, used by userdata/<id>/config/shortcuts.vdf
+#[derive(Clone, PartialEq, Debug)]
+pub enum Vdf {
+    Map(Vec<(String, Vdf)>),
+    Str(String),
+    Int(u32),
+    Float(f32),
+    Long(u64),
+}
+
+const VDF_MAP: u8 = 0x00;
+const VDF_STR: u8 = 0x01;
+const VDF_INT: u8 = 0x02;
+const VDF_FLOAT: u8 = 0x03;
+const VDF_LONG: u8 = 0x07;
+const VDF_END: u8 = 0x08;
+
+impl Vdf {
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Vdf> {
+        match self {
+            Vdf::Map(entries) => entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+// Parses a binary VDF file, whose top level is a map without a type or key of its own
+pub fn read_vdf(bytes: &[u8]) -> Result<Vdf, Box<dyn Error>> {
+    read_vdf_map(bytes, &mut 0)
+}
+
+fn read_vdf_map(bytes: &[u8], pos: &mut usize) -> Result<Vdf, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    loop {
+        let Some(&kind) = bytes.get(*pos) else {
+            // Some files don't end the top-level map
+            return Ok(Vdf::Map(entries));
+        };
+        *pos += 1;
+        if kind == VDF_END {
+            return Ok(Vdf::Map(entries));
+        }
+
+        let key = read_vdf_str(bytes, pos)?;
+        let value = match kind {
+            VDF_MAP => read_vdf_map(bytes, pos)?,
+            VDF_STR => Vdf::Str(read_vdf_str(bytes, pos)?),
+            VDF_INT => Vdf::Int(u32::from_le_bytes(read_vdf_bytes(bytes, pos)?)),
+            VDF_FLOAT => Vdf::Float(f32::from_le_bytes(read_vdf_bytes(bytes, pos)?)),
+            VDF_LONG => Vdf::Long(u64::from_le_bytes(read_vdf_bytes(bytes, pos)?)),
+            _ => return Err(format!("Unknown VDF type {kind:#04x} at byte {}", *pos).into()),
+        };
+        entries.push((key, value));
+    }
+}
+
+fn read_vdf_str(bytes: &[u8], pos: &mut usize) -> Result<String, Box<dyn Error>> {
+    let len = bytes[*pos..]
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("Unterminated string in VDF file")?;
+    let s = String::from_utf8_lossy(&bytes[*pos..*pos + len]).to_string();
+    *pos += len + 1;
+    Ok(s)
+}
+
+fn read_vdf_bytes<const N: usize>(
+    bytes: &[u8],
+    pos: &mut usize,
+) -> Result<[u8; N], Box<dyn Error>> {
+    let out = bytes
+        .get(*pos..*pos + N)
+        .ok_or("Unexpected end of VDF file")?
+        .try_into()?;
+    *pos += N;
+    Ok(out)
+}
+
+pub fn write_vdf(root: &Vdf) -> Vec<u8> {
+    let mut out = Vec::new();
+    if let Vdf::Map(entries) = root {
+        write_vdf_entries(entries, &mut out);
+    }
+    out
+}
+
+fn write_vdf_entries(entries: &[(String, Vdf)], out: &mut Vec<u8>) {
+    for (key, value) in entries {
+        let kind = match value {
+            Vdf::Map(_) => VDF_MAP,
+            Vdf::Str(_) => VDF_STR,
+            Vdf::Int(_) => VDF_INT,
+            Vdf::Float(_) => VDF_FLOAT,
+            Vdf::Long(_) => VDF_LONG,
+        };
+        out.push(kind);
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        match value {
+            Vdf::Map(entries) => write_vdf_entries(entries, out),
+            Vdf::Str(s) => {
+                out.extend_from_slice(s.as_bytes());
+                out.push(0);
+            }
+            Vdf::Int(n) => out.extend_from_slice(&n.to_le_bytes()),
+            Vdf::Float(n) => out.extend_from_slice(&n.to_le_bytes()),
+            Vdf::Long(n) => out.extend_from_slice(&n.to_le_bytes()),
+        }
+    }
+    out.push(VDF_END);
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+// The id Steam gives a non-Steam game, also used to name its artwork in the grid folder
+pub fn shortcut_appid(exe: &str, name: &str) -> u32 {
+    crc32(format!("{exe}{name}").as_bytes()) | 0x80000000
+}
+
+// Steam accounts that have logged in on this machine
+fn steam_user_dirs() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(PATH_STEAM.join("userdata")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name != "0" && name.chars().all(|c| c.is_ascii_digit()) && entry.path().is_dir()
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+fn shortcut_entry(
+    appid: u32,
+    name: &str,
+    exe: &str,
+    start_dir: &str,
+    icon: &str,
+    launch_options: &str,
+) -> Vdf {
+    let s = |v: &str| Vdf::Str(v.to_string());
+    Vdf::Map(vec![
+        ("appid".to_string(), Vdf::Int(appid)),
+        ("AppName".to_string(), s(name)),
+        ("Exe".to_string(), s(exe)),
+        ("StartDir".to_string(), s(start_dir)),
+        ("icon".to_string(), s(icon)),
+        ("ShortcutPath".to_string(), s("")),
+        ("LaunchOptions".to_string(), s(launch_options)),
+        ("IsHidden".to_string(), Vdf::Int(0)),
+        ("AllowDesktopConfig".to_string(), Vdf::Int(1)),
+        ("AllowOverlay".to_string(), Vdf::Int(1)),
+        ("OpenVR".to_string(), Vdf::Int(0)),
+        ("Devkit".to_string(), Vdf::Int(0)),
+        ("DevkitGameID".to_string(), s("")),
+        ("DevkitOverrideAppID".to_string(), Vdf::Int(0)),
+        ("LastPlayTime".to_string(), Vdf::Int(0)),
+        ("FlatpakAppID".to_string(), s("")),
+        ("tags".to_string(), Vdf::Map(Vec::new())),
+    ])
+}
+
+// Adds the shortcut to shortcuts.vdf, replacing the one with the same appid if it's already there
+fn add_shortcut_to_file(path: &Path, appid: u32, entry: Vdf) -> Result<(), Box<dyn Error>> {
+    let mut root = match std::fs::read(path) {
+        Ok(bytes) => read_vdf(&bytes)?,
+        Err(_) => Vdf::Map(Vec::new()),
+    };
+    if root.get("shortcuts").is_none()
+        && let Vdf::Map(entries) = &mut root
+    {
+        entries.push(("shortcuts".to_string(), Vdf::Map(Vec::new())));
+    }
+    let Some(Vdf::Map(shortcuts)) = root.get_mut("shortcuts") else {
+        return Err(format!("{} is not a shortcuts file", path.display()).into());
+    };
+
+    let existing = shortcuts.iter().position(|(_, s)| {
+        s.get("appid") == Some(&Vdf::Int(appid)) || s.get("appId") == Some(&Vdf::Int(appid))
+    });
+    match existing {
+        Some(i) => shortcuts[i].1 = entry,
+        None => {
+            let index = shortcuts.len().to_string();
+            shortcuts.push((index, entry));
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // The previous file is kept as shortcuts.vdf.bak
+    write_atomic(path, &write_vdf(&root), path.exists())
+}
+
+// Steam rewrites shortcuts.vdf from memory when it exits, dropping shortcuts added meanwhile
+fn steam_running() -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm")).is_ok_and(|comm| comm.trim() == "steam")
+    })
+}
+
+// Icon and screenshots of the game, used as the shortcut's artwork
+fn game_artwork(game: &Game) -> (Option<PathBuf>, Vec<PathBuf>) {
+    match game {
+        Game::Executable(entry) => (
+            Some(entry.path_icon()).filter(|path| path.exists()),
+            Vec::new(),
+        ),
+        Game::HandlerRef(h) => (
+            Some(h.asset("icon.png")).filter(|path| path.exists()),
+            h.img_paths.clone(),
+        ),
+    }
+}
+
+// Copies the game's artwork into the account's grid folder, named after the shortcut's appid.
+// Returns the path of the copied icon.
+fn copy_artwork(grid: &Path, appid: u32, game: &Game) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    std::fs::create_dir_all(grid)?;
+    let (icon, imgs) = game_artwork(game);
+
+    let mut icon_dest = None;
+    if let Some(icon) = icon {
+        let dest = grid.join(format!("{appid}_icon.png"));
+        std::fs::copy(icon, &dest)?;
+        icon_dest = Some(dest);
+    }
+    // The first screenshot doubles as the wide capsule and the hero banner
+    if let Some(img) = imgs.first() {
+        let ext = img.extension().unwrap_or_default().to_string_lossy();
+        std::fs::copy(img, grid.join(format!("{appid}.{ext}")))?;
+        std::fs::copy(img, grid.join(format!("{appid}_hero.{ext}")))?;
+    }
+    Ok(icon_dest)
+}
+
+// Adds a non-Steam game to every Steam account on this machine that starts the preset through
+// `partydeck launch <game uid> --preset <name>`. Steam has to be closed, and shows the shortcut
+// the next time it starts. Returns the name of the shortcut.
+pub fn add_steam_shortcut(preset: &Preset, game: &Game) -> Result<String, Box<dyn Error>> {
+    if steam_running() {
+        return Err(
+            "Steam is running. Close Steam first, as it overwrites its shortcuts when it exits."
+                .into(),
+        );
+    }
+    let user_dirs = steam_user_dirs();
+    if user_dirs.is_empty() {
+        return Err(format!(
+            "No Steam accounts found in {}",
+            PATH_STEAM.join("userdata").display()
+        )
+        .into());
+    }
+
+    let path_exe = std::env::current_exe()?;
+    let exe = format!("\"{}\"", path_exe.display());
+    let start_dir = match path_exe.parent() {
+        Some(dir) => format!("\"{}\"", dir.display()),
+        None => String::new(),
+    };
+    let name = format!("{} ({})", game.name(), preset.name);
+    let launch_options = format!("launch \"{}\" --preset \"{}\"", game.uid(), preset.name);
+    let appid = shortcut_appid(&exe, &name);
+
+    for user_dir in user_dirs {
+        let config = user_dir.join("config");
+        let icon = copy_artwork(&config.join("grid"), appid, game)?
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let entry = shortcut_entry(appid, &name, &exe, &start_dir, &icon, &launch_options);
+        add_shortcut_to_file(&config.join("shortcuts.vdf"), appid, entry)?;
+    }
+
+    Ok(name)
+}
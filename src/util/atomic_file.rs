@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// e.g. paths.json -> paths.json.bak
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut out = OsString::from(path.as_os_str());
+    out.push(suffix);
+    PathBuf::from(out)
+}
+
+// Locks <path>.lock. Held for a whole read-modify-write, so the GUI and a CLI command can't overwrite
+// each other's changes.
+pub fn lock_file(path: &Path, exclusive: bool) -> Result<File, Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(with_suffix(path, ".lock"))?;
+    match exclusive {
+        true => lock.lock()?,
+        false => lock.lock_shared()?,
+    }
+    Ok(lock)
+}
+
+// Writes to <path>.tmp and renames it over path, so a crash can't leave a half-written file.
+// If backup is set, the previous version is kept as <path>.bak; callers leave it unset when the
+// current file is broken, as it mustn't replace a good backup.
+pub fn write_atomic(path: &Path, contents: &[u8], backup: bool) -> Result<(), Box<dyn Error>> {
+    let path_tmp = with_suffix(path, ".tmp");
+
+    let mut file = File::create(&path_tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    if backup && path.exists() {
+        std::fs::copy(path, with_suffix(path, ".bak"))?;
+    }
+    std::fs::rename(&path_tmp, path)?;
+    Ok(())
+}
@@ -1,11 +1,10 @@
 use crate::handler::Handler;
 use crate::paths::*;
+use crate::util::libraries::find_library_install;
+use crate::util::paths_store::PathsStore;
 use rfd::FileDialog;
-use serde_json::Value;
 use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn copy_dir_recursive(
     src: &PathBuf,
@@ -56,30 +55,13 @@ pub fn copy_dir_recursive(
 }
 
 pub fn get_rootpath_handler(handler: &Handler) -> Result<String, Box<dyn Error>> {
-    if let Some(value) = find_saved_path(&handler.uid) {
+    if let Some(value) = find_rootpath_handler(handler) {
         return value;
     }
 
-    if let Some(appid) = &handler.steam_appid {
-        if let Ok(appid_number) = str::parse::<u32>(appid) {
-            if let Some((app, library)) = steamlocate::SteamDir::locate()?
-                .find_app(appid_number)
-                .ok()
-                .flatten()
-            {
-                let path = library.resolve_app_dir(&app);
-                if path.exists() {
-                    let pathstr = path.to_string_lossy().to_string();
-                    add_path(&handler.uid, &pathstr)?;
-                    return Ok(pathstr);
-                }
-            }
-        }
-    }
-
     // If we didn't get a path from the file, ask user for folder
     let path = FileDialog::new()
-        .set_title(format!("Locate folder for {}", handler.uid))
+        .set_title(format!("Locate folder for {}", handler.display()))
         .set_directory(&*PATH_HOME)
         .pick_folder()
         .ok_or_else(|| "No folder selected")?;
@@ -91,6 +73,54 @@ pub fn get_rootpath_handler(handler: &Handler) -> Result<String, Box<dyn Error>>
     Ok(result)
 }
 
+// Looks up the handler's root path without asking the user: first paths.json, then the Steam library,
+// then other launchers' libraries. Paths found in a library are saved to paths.json.
+pub fn find_rootpath_handler(handler: &Handler) -> Option<Result<String, Box<dyn Error>>> {
+    let (path, saved) = lookup_rootpath(handler)?;
+    if !saved && let Err(err) = add_path(&handler.uid, &path) {
+        return Some(Err(err));
+    }
+    Some(Ok(path))
+}
+
+// Same as find_rootpath_handler, but never writes paths.json, for scans and checks that shouldn't
+// change anything
+pub fn peek_rootpath_handler(handler: &Handler) -> Option<String> {
+    lookup_rootpath(handler).map(|(path, _)| path)
+}
+
+// The root path and whether it came from paths.json
+fn lookup_rootpath(handler: &Handler) -> Option<(String, bool)> {
+    if let Some(root) = &handler.root {
+        return Some((root.to_string_lossy().to_string(), true));
+    }
+
+    // A saved path is stale if the game has since been moved or deleted, so look for it again
+    if let Some(Ok(path)) = find_saved_path(&handler.uid) {
+        if handler.is_installed_in(Path::new(&path)) {
+            return Some((path, true));
+        }
+        println!(
+            "Saved path for {} doesn't contain {} anymore, looking for the game again",
+            handler.uid, handler.exec
+        );
+    }
+
+    if let Some(appid) = &handler.steam_appid
+        && let Ok(appid_number) = str::parse::<u32>(appid)
+        && let Some((app, library)) = steamlocate::SteamDir::locate()
+            .ok()
+            .and_then(|dir| dir.find_app(appid_number).ok().flatten())
+    {
+        let path = library.resolve_app_dir(&app);
+        if path.exists() {
+            return Some((path.to_string_lossy().to_string(), false));
+        }
+    }
+
+    find_library_install(handler).map(|path| (path.to_string_lossy().to_string(), false))
+}
+
 pub fn get_rootpath(uid: &str) -> Result<String, Box<dyn Error>> {
     if let Some(value) = find_saved_path(uid) {
         return value;
@@ -110,39 +140,28 @@ pub fn get_rootpath(uid: &str) -> Result<String, Box<dyn Error>> {
     Ok(result)
 }
 
-fn add_path(uid: &str, path: &String) -> Result<(), Box<dyn Error>> {
+pub fn add_path(uid: &str, path: &String) -> Result<(), Box<dyn Error>> {
     println!("Updating paths.json with {uid}: {path}");
-    let mut paths = if let Ok(file) = File::open(PATH_PARTY.join("paths.json")) {
-        serde_json::from_reader(BufReader::new(file))
-            .unwrap_or(Value::Object(serde_json::Map::new()))
-    } else {
-        Value::Object(serde_json::Map::new())
-    };
-
-    if let Value::Object(ref mut map) = paths {
-        map.insert(uid.to_string(), Value::String(path.clone()));
-        std::fs::write(
-            PATH_PARTY.join("paths.json"),
-            serde_json::to_string_pretty(&paths)?,
-        )?;
-    }
+    PathsStore::update(|store| store.roots.insert(uid.to_string(), path.clone()))?;
+    Ok(())
+}
+
+pub fn remove_path(uid: &str) -> Result<(), Box<dyn Error>> {
+    println!("Removing {uid} from paths.json");
+    PathsStore::update(|store| store.roots.remove(uid))?;
     Ok(())
 }
 
+// Every uid and root path saved in paths.json, sorted by uid
+pub fn saved_paths() -> Vec<(String, String)> {
+    PathsStore::load().roots.into_iter().collect()
+}
+
 fn find_saved_path(uid: &str) -> Option<Result<String, Box<dyn Error>>> {
     println!("Reading paths.json for root path of {uid}");
-    if let Ok(file) = File::open(PATH_PARTY.join("paths.json")) {
-        let reader = BufReader::new(file);
-        if let Ok(json) = serde_json::from_reader::<_, Value>(reader) {
-            if let Some(path) = json.get(uid) {
-                if let Some(path_str) = path.as_str() {
-                    println!("Found root path for {uid}: {path_str}");
-                    return Some(Ok(path_str.to_string()));
-                }
-            }
-        }
-    }
-    None
+    let path = PathsStore::load().roots.remove(uid)?;
+    println!("Found root path for {uid}: {path}");
+    Some(Ok(path))
 }
 
 pub trait SanitizePath {
@@ -161,16 +180,14 @@ impl SanitizePath for String {
         // Allow single quotes in paths since they are quoted when launching
         // commands. Double quotes would break the quoting though, so we still
         // strip those along with other potentially dangerous characters.
-        let chars_to_sanitize = [
-            ';', '&', '|', '$', '`', '(', ')', '<', '>', '"', '\\', '/',
-        ];
+        // Dollar signs are kept for template variables, which are checked again after expansion.
+        let chars_to_sanitize = [';', '&', '|', '`', '(', ')', '<', '>', '"', '\\', '/'];
 
         if chars_to_sanitize.iter().any(|&c| sanitized.contains(c)) {
             sanitized = sanitized
                 .replace(";", "")
                 .replace("&", "")
                 .replace("|", "")
-                .replace("$", "")
                 .replace("`", "")
                 .replace("(", "")
                 .replace(")", "")
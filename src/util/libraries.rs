@@ -0,0 +1,233 @@
+use crate::handler::Handler;
+use crate::paths::*;
+
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+// Looks for the game in the libraries of other launchers, using the ids the handler declares
+pub fn find_library_install(h: &Handler) -> Option<PathBuf> {
+    if h.exec.is_empty() {
+        return None;
+    }
+    if let Some(id) = &h.gog_id
+        && let Some(path) = find_heroic_gog(id, h)
+    {
+        return Some(path);
+    }
+    if let Some(appname) = &h.epic_appname
+        && let Some(path) = find_heroic_epic(appname, h)
+    {
+        return Some(path);
+    }
+    if let Some(slug) = &h.lutris_slug
+        && let Some(path) = find_lutris(slug, h)
+    {
+        return Some(path);
+    }
+    if let Some(program) = &h.bottles_program
+        && let Some(path) = find_bottles(program, h)
+    {
+        return Some(path);
+    }
+    if let Some(id) = &h.itch_id
+        && let Some(path) = find_itch(id, h)
+    {
+        return Some(path);
+    }
+    None
+}
+
+// The install folder counts only if the handler's executable is in it
+fn root_from_dir(dir: &Path, h: &Handler) -> Option<PathBuf> {
+    dir.join(&h.exec).exists().then(|| dir.to_path_buf())
+}
+
+// Launchers that store the full executable path: the root is what's left after removing game.exec
+fn root_from_exe(exe: &str, h: &Handler) -> Option<PathBuf> {
+    let root = exe.strip_suffix(h.exec.as_str())?.strip_suffix('/')?;
+    root_from_dir(Path::new(root), h)
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_reader(BufReader::new(File::open(path).ok()?)).ok()
+}
+
+// Heroic keeps its config in ~/.config, or in its sandbox when installed as a Flatpak
+fn heroic_dirs() -> [PathBuf; 2] {
+    [
+        PATH_HOME.join(".config/heroic"),
+        PATH_HOME.join(".var/app/com.heroicgameslauncher.hgl/config/heroic"),
+    ]
+}
+
+fn find_heroic_gog(id: &str, h: &Handler) -> Option<PathBuf> {
+    for dir in heroic_dirs() {
+        let Some(json) = read_json(&dir.join("gog_store/installed.json")) else {
+            continue;
+        };
+        for game in json["installed"].as_array().into_iter().flatten() {
+            if game["appName"].as_str() == Some(id)
+                && let Some(path) = game["install_path"].as_str()
+                && let Some(root) = root_from_dir(Path::new(path), h)
+            {
+                return Some(root);
+            }
+        }
+    }
+    None
+}
+
+fn find_heroic_epic(appname: &str, h: &Handler) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = heroic_dirs()
+        .iter()
+        .map(|dir| dir.join("legendaryConfig/legendary/installed.json"))
+        .collect();
+    files.push(PATH_HOME.join(".config/legendary/installed.json"));
+
+    for file in files {
+        if let Some(json) = read_json(&file)
+            && let Some(path) = json[appname]["install_path"].as_str()
+            && let Some(root) = root_from_dir(Path::new(path), h)
+        {
+            return Some(root);
+        }
+    }
+    None
+}
+
+// Strips the quotes YAML may put around a scalar value
+fn yaml_value(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .unwrap_or(value)
+}
+
+// Lutris names game configs <slug>-<timestamp>.yml and stores the executable path under game: exe:
+fn find_lutris(slug: &str, h: &Handler) -> Option<PathBuf> {
+    let dirs = [
+        PATH_LOCAL_SHARE.join("lutris/games"),
+        PATH_HOME.join(".config/lutris/games"),
+        PATH_HOME.join(".var/app/net.lutris.Lutris/data/lutris/games"),
+    ];
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(stem) = name.strip_suffix(".yml") else {
+                continue;
+            };
+            let matches = stem == slug
+                || stem.strip_prefix(slug).is_some_and(|rest| {
+                    rest.strip_prefix('-')
+                        .is_some_and(|ts| !ts.is_empty() && ts.chars().all(|c| c.is_ascii_digit()))
+                });
+            if !matches {
+                continue;
+            }
+
+            let Ok(contents) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            for line in contents.lines() {
+                let line = line.trim();
+                if let Some(exe) = line.strip_prefix("exe:")
+                    && let Some(root) = root_from_exe(yaml_value(exe), h)
+                {
+                    return Some(root);
+                }
+                if let Some(dir) = line.strip_prefix("working_dir:")
+                    && let Some(root) = root_from_dir(Path::new(yaml_value(dir)), h)
+                {
+                    return Some(root);
+                }
+            }
+        }
+    }
+    None
+}
+
+// Bottles lists programs added to a bottle under External_Programs in bottle.yml,
+// one entry per program with its name and full executable path
+fn find_bottles(program: &str, h: &Handler) -> Option<PathBuf> {
+    let dirs = [
+        PATH_LOCAL_SHARE.join("bottles/bottles"),
+        PATH_HOME.join(".var/app/com.usebottles.bottles/data/bottles/bottles"),
+    ];
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(contents) = std::fs::read_to_string(entry.path().join("bottle.yml")) else {
+                continue;
+            };
+
+            let mut in_programs = false;
+            let mut name = String::new();
+            let mut path = String::new();
+            let check = |name: &str, path: &str| -> Option<PathBuf> {
+                match name == program {
+                    true => root_from_exe(path, h),
+                    false => None,
+                }
+            };
+
+            for line in contents.lines() {
+                let indent = line.len() - line.trim_start().len();
+                let line = line.trim();
+                if indent == 0 {
+                    in_programs = line == "External_Programs:";
+                    continue;
+                }
+                if !in_programs {
+                    continue;
+                }
+                // A new program entry starts at the first indentation level
+                if indent <= 2 {
+                    if let Some(root) = check(&name, &path) {
+                        return Some(root);
+                    }
+                    name.clear();
+                    path.clear();
+                } else if let Some(value) = line.strip_prefix("name:") {
+                    name = yaml_value(value).to_string();
+                } else if let Some(value) = line.strip_prefix("path:") {
+                    path = yaml_value(value).to_string();
+                }
+            }
+            if let Some(root) = check(&name, &path) {
+                return Some(root);
+            }
+        }
+    }
+    None
+}
+
+// The itch app writes a gzipped receipt with the game's id into every install folder
+fn find_itch(id: &str, h: &Handler) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(PATH_HOME.join(".config/itch/apps")).ok()?;
+    for entry in entries.flatten() {
+        let Ok(file) = File::open(entry.path().join(".itch/receipt.json.gz")) else {
+            continue;
+        };
+        let Ok(receipt) = serde_json::from_reader::<_, Value>(GzDecoder::new(file)) else {
+            continue;
+        };
+        let game_id = &receipt["game"]["id"];
+        if (game_id.as_u64().map(|n| n.to_string()).as_deref() == Some(id)
+            || game_id.as_str() == Some(id))
+            && let Some(root) = root_from_dir(&entry.path(), h)
+        {
+            return Some(root);
+        }
+    }
+    None
+}
@@ -1,14 +1,34 @@
 // Re-export all utility functions from submodules
+mod atomic_file;
 mod filesystem;
+mod libraries;
+mod paths_store;
 mod profiles;
+mod proton;
+mod steam_apps;
 mod sys;
+mod template;
 mod updates;
 
+pub use atomic_file::{lock_file, with_suffix, write_atomic};
+pub use paths_store::PathsStore;
+
 // Re-export functions from profiles
-pub use profiles::{create_gamesave, create_profile, remove_guest_profiles, scan_profiles};
+pub use profiles::{
+    create_gamesave, create_profile, export_gamesave, gamesave_mappings, guest_names,
+    import_gamesave, profile_setting, remove_guest_profiles, scan_profiles,
+};
+
+// Re-export functions from proton
+pub use proton::{ProtonInstall, apply_prefix_setup, resolve_proton_path, scan_proton_installs};
 
 // Re-export functions from filesystem
-pub use filesystem::{SanitizePath, copy_dir_recursive, get_rootpath, get_rootpath_handler};
+pub use filesystem::{
+    SanitizePath, add_path, copy_dir_recursive, find_rootpath_handler, get_rootpath,
+    get_rootpath_handler, peek_rootpath_handler, remove_path, saved_paths,
+};
+
+pub use steam_apps::{SteamApp, scan_steam_apps, steam_app_icon};
 
 // Re-export functions from launcher
 pub use sys::{
@@ -16,5 +36,10 @@ pub use sys::{
     kwin_dbus_unload_script, msg, yesno,
 };
 
+// Re-export functions from template
+pub use template::{
+    TemplateVars, expand_template, expand_template_path, placeholder_vars, render_templates,
+};
+
 // Re-export functions from updates
 pub use updates::{check_for_partydeck_update, update_goldberg_emu, update_umu_launcher};
@@ -0,0 +1,97 @@
+use crate::executable::{ExecutableEntry, deserialize_executables};
+use crate::paths::*;
+use crate::util::{lock_file, with_suffix, write_atomic};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+// Saved game locations and added executables, stored in paths.json
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct PathsStore {
+    // Game folder of each handler uid (and of Steam itself, under "steam")
+    #[serde(default)]
+    pub roots: BTreeMap<String, String>,
+    // Executables added with Add Game
+    #[serde(default, deserialize_with = "deserialize_executables")]
+    pub executables: Vec<ExecutableEntry>,
+}
+
+fn path_store() -> PathBuf {
+    PATH_PARTY.join("paths.json")
+}
+
+impl PathsStore {
+    // Reads paths.json, falling back to the backup if it's unreadable
+    pub fn load() -> Self {
+        let _lock = lock_file(&path_store(), false);
+        Self::read()
+    }
+
+    // Loads the store, applies f and saves the result, all under one lock
+    pub fn update<T>(f: impl FnOnce(&mut Self) -> T) -> Result<T, Box<dyn Error>> {
+        let _lock = lock_file(&path_store(), true)?;
+        let mut store = Self::read();
+        let out = f(&mut store);
+        store.write()?;
+        Ok(out)
+    }
+
+    fn read() -> Self {
+        let path = path_store();
+        for file in [path.clone(), with_suffix(&path, ".bak")] {
+            if !file.exists() {
+                continue;
+            }
+            match Self::read_file(&file) {
+                Ok(store) => return store,
+                Err(err) => println!("Couldn't read {}: {err}", file.display()),
+            }
+        }
+        Self::default()
+    }
+
+    fn read_file(file: &PathBuf) -> Result<Self, Box<dyn Error>> {
+        let json: Value = serde_json::from_reader(BufReader::new(File::open(file)?))?;
+        if json.get("roots").is_some_and(Value::is_object) {
+            return Ok(serde_json::from_value(json)?);
+        }
+
+        // Older versions kept uids as top-level keys and executables under ".executables"
+        let mut store = Self::default();
+        let Value::Object(map) = json else {
+            return Err("paths.json is not an object".into());
+        };
+        for (key, value) in map {
+            match (key.as_str(), value) {
+                (".executables", Value::Array(executables)) => {
+                    store.executables = executables
+                        .iter()
+                        .filter_map(|p| p.as_str().map(ExecutableEntry::new))
+                        .collect();
+                }
+                (_, Value::String(path)) => {
+                    store.roots.insert(key, path);
+                }
+                _ => {}
+            }
+        }
+        Ok(store)
+    }
+
+    // Writes to a temporary file and renames it over paths.json, keeping the previous version as paths.json.bak
+    fn write(&self) -> Result<(), Box<dyn Error>> {
+        let path = path_store();
+        // A broken paths.json mustn't replace a good backup
+        let backup = Self::read_file(&path).is_ok();
+        write_atomic(
+            &path,
+            serde_json::to_string_pretty(self)?.as_bytes(),
+            backup,
+        )
+    }
+}
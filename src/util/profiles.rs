@@ -2,7 +2,8 @@ use rand::prelude::*;
 use std::error::Error;
 use std::path::PathBuf;
 
-use crate::util::filesystem::copy_dir_recursive;
+use crate::app::PartyConfig;
+use crate::util::filesystem::{copy_dir_recursive, get_rootpath_handler};
 use crate::{handler::Handler, paths::*};
 
 // Makes a folder and sets up Goldberg Steam Emu profile for Steam games
@@ -25,6 +26,21 @@ pub fn create_profile(name: &str) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+// Reads a value from the profile's Goldberg configs.user.ini, such as account_steamid or language
+pub fn profile_setting(name: &str, key: &str) -> Option<String> {
+    let path = PATH_PARTY
+        .join("profiles")
+        .join(name)
+        .join("steam/settings/configs.user.ini");
+    std::fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        })
+}
+
 // Creates the "game save" folder for per-profile game data to go into
 pub fn create_gamesave(name: &str, h: &Handler) -> Result<(), Box<dyn Error>> {
     let path_gamesave = PATH_PARTY
@@ -55,7 +71,8 @@ pub fn create_gamesave(name: &str, h: &Handler) -> Result<(), Box<dyn Error>> {
     }
 
     for path in &h.game_unique_paths {
-        if path.is_empty() {
+        // Templated paths differ per instance, so they're created at launch
+        if path.is_empty() || path.contains('$') {
             continue;
         }
         // If the path contains a dot, we assume it to be a file, and don't create a directory,
@@ -107,6 +124,23 @@ pub fn scan_profiles(include_guest: bool) -> Vec<String> {
     out
 }
 
+pub static GUEST_NAMES: [&str; 21] = [
+    "Blinky", "Pinky", "Inky", "Clyde", "Beatrice", "Battler", "Ellie", "Joel", "Leon", "Ada",
+    "Madeline", "Theo", "Yokatta", "Wyrm", "Brodiee", "Supreme", "Conk", "Gort", "Lich", "Smores",
+    "Canary",
+];
+
+// Profile names for the given number of guests, e.g. ".Blinky", each picked at most once
+pub fn guest_names(count: usize) -> Vec<String> {
+    let mut names = GUEST_NAMES.to_vec();
+    let mut out = Vec::new();
+    while out.len() < count && !names.is_empty() {
+        let i = fastrand::usize(..names.len());
+        out.push(format!(".{}", names.swap_remove(i)));
+    }
+    out
+}
+
 pub fn remove_guest_profiles() -> Result<(), Box<dyn Error>> {
     let path_profiles = PATH_PARTY.join("profiles");
     let entries = std::fs::read_dir(&path_profiles)?;
@@ -124,3 +158,142 @@ pub fn remove_guest_profiles() -> Result<(), Box<dyn Error>> {
     }
     Ok(())
 }
+
+// Pairs each of the handler's save locations in its normal (non-PartyDeck) install with the matching
+// location in the profile's game save. Whole-directory binds (AppData, Documents, ~/.local/share, ~/.config)
+// map to their root, so anything picked from inside them keeps its relative path.
+pub fn gamesave_mappings(
+    name: &str,
+    h: &Handler,
+    cfg: &PartyConfig,
+) -> Result<Vec<(PathBuf, PathBuf)>, Box<dyn Error>> {
+    let path_prof = PATH_PARTY.join("profiles").join(name);
+    let path_gamesave = path_prof.join("saves").join(&h.uid);
+    let mut out = Vec::new();
+
+    if h.win {
+        // Prefer the game's own Steam Proton prefix, falling back to the PartyDeck prefix
+        let path_windata = match &h.steam_appid {
+            Some(appid)
+                if PATH_STEAM
+                    .join(format!("steamapps/compatdata/{appid}"))
+                    .exists() =>
+            {
+                PATH_STEAM.join(format!("steamapps/compatdata/{appid}/pfx"))
+            }
+            _ => h.path_prefix(cfg),
+        }
+        .join("drive_c/users/steamuser");
+
+        if h.win_unique_appdata {
+            out.push((path_windata.join("AppData"), path_gamesave.join("_AppData")));
+        }
+        if h.win_unique_documents {
+            out.push((
+                path_windata.join("Documents"),
+                path_gamesave.join("_Documents"),
+            ));
+        }
+    } else {
+        if h.linux_unique_localshare {
+            out.push((PATH_LOCAL_SHARE.clone(), path_gamesave.join("_share")));
+        }
+        if h.linux_unique_config {
+            out.push((PATH_HOME.join(".config"), path_gamesave.join("_config")));
+        }
+    }
+
+    if !h.game_unique_paths.is_empty() {
+        let path_root = PathBuf::from(get_rootpath_handler(h)?);
+        for path in &h.game_unique_paths {
+            if path.is_empty() || path.contains('$') {
+                continue;
+            }
+            out.push((path_root.join(path), path_gamesave.join(path)));
+        }
+    }
+
+    // Steam Cloud saves live in userdata/<account>/<appid>/remote; Goldberg keeps them in <save path>/<appid>/remote
+    if let Some(appid) = &h.steam_appid
+        && !h.path_goldberg.is_empty()
+        && let Ok(entries) = std::fs::read_dir(PATH_STEAM.join("userdata"))
+    {
+        for entry in entries.flatten() {
+            let remote = entry.path().join(appid).join("remote");
+            if remote.exists() {
+                out.push((remote, path_prof.join("steam").join(appid).join("remote")));
+                break;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+// Copies a file or folder from the game's normal save locations into the profile's game save.
+// src must be inside one of the locations returned by gamesave_mappings.
+pub fn import_gamesave(
+    name: &str,
+    h: &Handler,
+    cfg: &PartyConfig,
+    src: &PathBuf,
+) -> Result<PathBuf, Box<dyn Error>> {
+    create_profile(name)?;
+    create_gamesave(name, h)?;
+
+    // Locations can be nested, e.g. the game folder inside ~/.local/share, so the deepest one wins
+    let (path_native, path_save) = gamesave_mappings(name, h, cfg)?
+        .into_iter()
+        .filter(|(path_native, _)| src.starts_with(path_native))
+        .max_by_key(|(path_native, _)| path_native.components().count())
+        .ok_or("Selected path isn't inside any of this game's save locations")?;
+
+    let dest = path_save.join(src.strip_prefix(&path_native)?);
+    println!(
+        "Importing save data {} -> {}",
+        src.display(),
+        dest.display()
+    );
+    copy_save_data(src, &dest)?;
+    Ok(dest)
+}
+
+// The reverse of import_gamesave: copies a file or folder from the profile's game save
+// back to the game's normal save location.
+pub fn export_gamesave(
+    name: &str,
+    h: &Handler,
+    cfg: &PartyConfig,
+    src: &PathBuf,
+) -> Result<PathBuf, Box<dyn Error>> {
+    // Like import_gamesave, the deepest matching folder wins
+    let (path_native, path_save) = gamesave_mappings(name, h, cfg)?
+        .into_iter()
+        .filter(|(_, path_save)| src.starts_with(path_save))
+        .max_by_key(|(_, path_save)| path_save.components().count())
+        .ok_or("Selected path isn't inside any of this profile's save folders")?;
+
+    let dest = path_native.join(src.strip_prefix(&path_save)?);
+    println!(
+        "Exporting save data {} -> {}",
+        src.display(),
+        dest.display()
+    );
+    copy_save_data(src, &dest)?;
+    Ok(dest)
+}
+
+fn copy_save_data(src: &PathBuf, dest: &PathBuf) -> Result<(), Box<dyn Error>> {
+    if src.is_dir() {
+        std::fs::create_dir_all(dest)?;
+        copy_dir_recursive(src, dest, false, true)?;
+    } else if src.is_file() {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(src, dest)?;
+    } else {
+        return Err(format!("{} doesn't exist", src.display()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use crate::app::PartyConfig;
+use crate::handler::Handler;
+use crate::paths::*;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+// Names UMU resolves and downloads by itself when passed as PROTONPATH
+const UMU_PROTON_NAMES: [&str; 4] = ["GE-Proton", "GE-Latest", "UMU-Proton", "UMU-Latest"];
+
+#[derive(Clone)]
+pub struct ProtonInstall {
+    pub name: String,
+    pub path: PathBuf,
+    pub version: String,
+}
+
+impl ProtonInstall {
+    pub fn display(&self) -> String {
+        if self.version.is_empty() || self.version == self.name {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, self.version)
+        }
+    }
+}
+
+fn proton_search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PATH_STEAM.join("steamapps/common"),
+        PATH_STEAM.join("compatibilitytools.d"),
+        PATH_HOME.join(".steam/root/compatibilitytools.d"),
+        // Flatpak Steam
+        PATH_HOME.join(".var/app/com.valvesoftware.Steam/.local/share/Steam/compatibilitytools.d"),
+        PATH_HOME.join(".var/app/com.valvesoftware.Steam/data/Steam/compatibilitytools.d"),
+        // UMU downloads its Proton builds here even without Steam installed
+        PATH_LOCAL_SHARE.join("Steam/compatibilitytools.d"),
+        PATH_LOCAL_SHARE.join("umu/compatibilitytools"),
+    ];
+
+    let cache = match std::env::var("XDG_CACHE_HOME") {
+        Ok(xdg_cache_home) => PathBuf::from(xdg_cache_home),
+        Err(_) => PATH_HOME.join(".cache"),
+    };
+    dirs.push(cache.join("umu"));
+
+    // Proton versions installed by Steam can also be in other library folders
+    if let Ok(steamdir) = steamlocate::SteamDir::locate()
+        && let Ok(libraries) = steamdir.libraries()
+    {
+        for library in libraries.flatten() {
+            dirs.push(library.path().join("steamapps/common"));
+        }
+    }
+
+    dirs
+}
+
+// Finds Proton builds in Steam's libraries, compatibilitytools.d (native and Flatpak) and UMU's directories
+pub fn scan_proton_installs() -> Vec<ProtonInstall> {
+    let mut out: Vec<ProtonInstall> = Vec::new();
+    let mut seen: Vec<PathBuf> = Vec::new();
+
+    for dir in proton_search_dirs() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() || !path.join("proton").exists() {
+                continue;
+            }
+            let canonical = path.canonicalize().unwrap_or(path.clone());
+            if seen.contains(&canonical) {
+                continue;
+            }
+            seen.push(canonical);
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            // The version file contains "<build timestamp> <version name>"
+            let version = std::fs::read_to_string(path.join("version"))
+                .ok()
+                .and_then(|v| v.split_whitespace().last().map(|s| s.to_string()))
+                .unwrap_or_default();
+
+            out.push(ProtonInstall {
+                name,
+                path,
+                version,
+            });
+        }
+    }
+
+    out.sort_by_key(|a| a.name.to_lowercase());
+    out
+}
+
+// Turns the Proton version setting into a PROTONPATH value, making sure the chosen build exists.
+pub fn resolve_proton_path(proton_version: &str) -> Result<String, Box<dyn Error>> {
+    let proton_version = proton_version.trim();
+    if proton_version.is_empty() {
+        return Ok("GE-Proton".to_string());
+    }
+
+    if proton_version.contains('/') {
+        let mut path = PathBuf::from(proton_version);
+        // Older settings point at the proton script itself rather than its folder
+        if path.is_file()
+            && path.file_name().is_some_and(|name| name == "proton")
+            && let Some(parent) = path.parent()
+        {
+            path = parent.to_path_buf();
+        }
+        if !path.join("proton").exists() {
+            return Err(format!("Proton build not found: {proton_version}").into());
+        }
+        return Ok(path.to_string_lossy().to_string());
+    }
+
+    // Specific GE-Proton releases can also be fetched by UMU
+    if UMU_PROTON_NAMES.contains(&proton_version) || proton_version.starts_with("GE-Proton") {
+        return Ok(proton_version.to_string());
+    }
+
+    if let Some(install) = scan_proton_installs()
+        .into_iter()
+        .find(|p| p.name == proton_version || p.version == proton_version)
+    {
+        return Ok(install.path.to_string_lossy().to_string());
+    }
+
+    Err(format!("Proton version \"{proton_version}\" is not installed").into())
+}
+
+// Runs the handler's prefix setup steps (winetricks verbs, or .reg files shipped in the handler folder)
+// through UMU. Steps that were already applied to the prefix are tracked in a state file and skipped.
+// Each step is reported through progress before it runs.
+pub fn apply_prefix_setup(
+    h: &Handler,
+    cfg: &PartyConfig,
+    progress: &dyn Fn(&str),
+) -> Result<(), Box<dyn Error>> {
+    if !h.win || h.prefix_setup.is_empty() {
+        return Ok(());
+    }
+
+    let pfx = h.path_prefix(cfg);
+    std::fs::create_dir_all(&pfx)?;
+
+    let path_state = pfx.join("partydeck_setup.json");
+    let mut applied: Vec<String> = std::fs::read_to_string(&path_state)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+
+    let pending: Vec<&String> = h
+        .prefix_setup
+        .iter()
+        .filter(|step| !applied.contains(step))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let protonpath = resolve_proton_path(&cfg.proton_version)?;
+
+    for (i, step) in pending.iter().enumerate() {
+        let action = match step.ends_with(".reg") {
+            true => format!("Importing registry file {step}"),
+            false => format!("Installing {step} with winetricks"),
+        };
+        let status = format!("Setting up prefix ({}/{}): {action}", i + 1, pending.len());
+        println!("{status} for {}", h.uid);
+        progress(&status);
+
+        let mut cmd = std::process::Command::new(PATH_RES.join("umu-run"));
+        cmd.env("WINEPREFIX", &pfx).env("PROTONPATH", &protonpath);
+
+        if step.ends_with(".reg") {
+            let path_reg = h.asset(step);
+            if !path_reg.exists() {
+                return Err(format!("Registry file not found: {}", path_reg.display()).into());
+            }
+            cmd.env("PROTON_VERB", "run")
+                .arg("regedit")
+                .arg("/S")
+                .arg(path_reg);
+        } else {
+            cmd.arg("winetricks").arg("-q").arg(step.as_str());
+        }
+
+        let status = cmd.status()?;
+        if !status.success() {
+            return Err(format!("Prefix setup step \"{step}\" failed").into());
+        }
+
+        // Save after each step so a failed step doesn't cause the previous ones to run again
+        applied.push(step.to_string());
+        std::fs::write(&path_state, serde_json::to_string_pretty(&applied)?)?;
+    }
+
+    Ok(())
+}
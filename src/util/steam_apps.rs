@@ -0,0 +1,131 @@
+use crate::paths::*;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+// An installed game found in the Steam libraries, for the Import from Steam page
+#[derive(Clone)]
+pub struct SteamApp {
+    pub appid: u32,
+    pub name: String,
+    pub path_root: PathBuf,
+    // Guessed with guess_executable, can be changed before importing
+    pub exe: Option<PathBuf>,
+    pub selected: bool,
+}
+
+// Steam's own tools show up as installed apps, but aren't games
+fn is_steam_tool(name: &str, appid: u32) -> bool {
+    appid == 228980 // Steamworks Common Redistributables
+        || name.starts_with("Proton")
+        || name.starts_with("Steam Linux Runtime")
+        || name.starts_with("Steamworks")
+}
+
+// Every installed app in every Steam library folder, sorted by name
+pub fn scan_steam_apps() -> Vec<SteamApp> {
+    let mut out = Vec::new();
+    let Ok(steam_dir) = steamlocate::SteamDir::locate() else {
+        println!("Steam installation not found");
+        return out;
+    };
+    let Ok(libraries) = steam_dir.libraries() else {
+        return out;
+    };
+
+    for library in libraries.flatten() {
+        for app in library.apps().flatten() {
+            let name = app.name.clone().unwrap_or(app.install_dir.clone());
+            if is_steam_tool(&name, app.app_id) {
+                continue;
+            }
+            let path_root = library.resolve_app_dir(&app);
+            if !path_root.is_dir() {
+                continue;
+            }
+            out.push(SteamApp {
+                appid: app.app_id,
+                exe: guess_executable(&path_root, &name),
+                name,
+                path_root,
+                selected: false,
+            });
+        }
+    }
+
+    out.sort_by_key(|app| app.name.to_lowercase());
+    out
+}
+
+// Files that come with games but aren't the game itself
+const EXE_IGNORE: &[&str] = &[
+    "unins",
+    "setup",
+    "install",
+    "redist",
+    "crash",
+    "report",
+    "dxsetup",
+    "vcredist",
+    "dotnet",
+    "physx",
+    "ue4prereq",
+    "easyanticheat",
+    "benchmark",
+    "config",
+];
+
+// Picks the most likely game executable in dir: Windows executables or native Linux binaries,
+// preferring names that match the game's name, shallow paths and large files
+pub fn guess_executable(dir: &Path, name: &str) -> Option<PathBuf> {
+    let name_words: Vec<String> = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| word.len() > 1)
+        .map(|word| word.to_string())
+        .collect();
+
+    walkdir::WalkDir::new(dir)
+        .max_depth(4)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let metadata = entry.metadata().ok()?;
+            let filename = path.file_name()?.to_string_lossy().to_lowercase();
+            if EXE_IGNORE.iter().any(|word| filename.contains(word)) {
+                return None;
+            }
+            let ext = path
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            let is_exe = match ext.as_str() {
+                "exe" => true,
+                "" | "x86_64" | "x86" | "sh" => metadata.permissions().mode() & 0o111 != 0,
+                _ => false,
+            };
+            if !is_exe {
+                return None;
+            }
+
+            let matches = name_words
+                .iter()
+                .filter(|word| filename.contains(word.as_str()));
+            let score = matches.count() as u64 * 1_000_000_000
+                + metadata.len() / (entry.depth() as u64 + 1);
+            Some((score, path.to_path_buf()))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, path)| path)
+}
+
+// The game's icon from Steam's library cache, if Steam has downloaded it
+pub fn steam_app_icon(appid: u32) -> Option<PathBuf> {
+    let path = PATH_STEAM
+        .join("appcache/librarycache")
+        .join(format!("{appid}_icon.jpg"));
+    path.exists().then_some(path)
+}
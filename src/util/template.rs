@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+// Variables handlers can use in game.args, game.env, game.exec, path fields and template files
+pub const TEMPLATE_VARS: &[&str] = &[
+    "GAMEDIR",
+    "PROFILE",
+    "PROFILE_DIR",
+    "SAVEDIR",
+    "WIDTH",
+    "HEIGHT",
+    "WIDTHXHEIGHT",
+    "PLAYER_INDEX",
+    "PLAYER_COUNT",
+    "CONTROLLER_INDEX",
+    "STEAMID",
+    "LANG",
+];
+
+pub type TemplateVars = HashMap<&'static str, String>;
+
+fn is_var_char(c: char) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'
+}
+
+// Replaces $NAME and ${NAME} with their values. Names are the longest run of uppercase letters,
+// digits and underscores, so "--res=$WIDTHx$HEIGHT" works. "$$" is a literal dollar sign.
+pub fn expand_template(input: &str, vars: &TemplateVars) -> Result<String, Box<dyn Error>> {
+    let mut out = String::new();
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        let name = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| format!("Unclosed \"${{\" in \"{input}\""))?;
+            rest = &braced[end + 1..];
+            &braced[..end]
+        } else if let Some(escaped) = rest.strip_prefix('$') {
+            out.push('$');
+            rest = escaped;
+            continue;
+        } else {
+            let end = rest.find(|c| !is_var_char(c)).unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+            name
+        };
+
+        if name.is_empty() {
+            return Err(format!("Missing variable name after \"$\" in \"{input}\"").into());
+        }
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None => {
+                return Err(format!(
+                    "Unknown variable ${name} in \"{input}\". Available variables: {}",
+                    TEMPLATE_VARS.join(", ")
+                )
+                .into());
+            }
+        }
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+// Expands a path relative to the game folder. The result goes inside double quotes in the launch
+// command, so anything the shell would still interpret there is rejected.
+pub fn expand_template_path(input: &str, vars: &TemplateVars) -> Result<String, Box<dyn Error>> {
+    let path = expand_template(input, vars)?;
+    if path.contains(['"', '`', '$', '\\']) || path.split('/').any(|part| part == "..") {
+        return Err(format!("\"{input}\" expands to an invalid path \"{path}\"").into());
+    }
+    Ok(path)
+}
+
+// Vars with every known name set, for checking templates without launching
+pub fn placeholder_vars() -> TemplateVars {
+    TEMPLATE_VARS
+        .iter()
+        .map(|&name| (name, name.to_lowercase()))
+        .collect()
+}
+
+// Renders every .tmpl file in src into dest, keeping the folder layout and dropping the extension,
+// so templates/settings.ini.tmpl becomes <dest>/settings.ini. Literal dollar signs have to be written as "$$".
+pub fn render_templates(
+    src: &PathBuf,
+    dest: &PathBuf,
+    vars: &TemplateVars,
+) -> Result<(), Box<dyn Error>> {
+    if !src.is_dir() {
+        return Ok(());
+    }
+
+    for entry in walkdir::WalkDir::new(src).min_depth(1) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().unwrap_or_default() != "tmpl" {
+            continue;
+        }
+
+        let rel_path = path.strip_prefix(src)?.with_extension("");
+        let contents = std::fs::read_to_string(path)?;
+        let rendered = expand_template(&contents, vars)
+            .map_err(|err| format!("{}: {err}", rel_path.display()))?;
+
+        let path_out = dest.join(&rel_path);
+        if let Some(parent) = path_out.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path_out, rendered)?;
+    }
+
+    Ok(())
+}